@@ -1,8 +1,14 @@
+use crate::flightlistgenerator::FlightListCache;
+use crate::pages::events::ReloadEvents;
 use crate::renderable::filters::FlightFilter;
+use crate::renderable::logbook::{Pagination, PilotIdentity};
 use async_std::{sync::Arc, sync::Mutex};
 // todo rewrite with a singe arc mutex
 #[derive(Clone)]
 pub struct AppState {
-    pub filename: Arc<Mutex<String>>,
+    pub flight_list: Arc<Mutex<FlightListCache>>,
     pub filter: Arc<Mutex<FlightFilter>>,
+    pub pagination: Arc<Mutex<Pagination>>,
+    pub pilot: Arc<Mutex<PilotIdentity>>,
+    pub reload_events: Arc<ReloadEvents>,
 }
@@ -1,11 +1,19 @@
 use anyhow::bail;
 use async_std::{self, sync::Mutex};
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use zenjep::applicationstate::AppState;
+use zenjep::commands::check::{check_logbook, CheckFormat};
+use zenjep::commands::export::{export_csv, export_pdf, export_xlsx};
+use zenjep::commands::import::{import_csv, import_export_file};
+use zenjep::flightlistgenerator::diagnostic::Severity;
+use zenjep::flightlistgenerator::flighttime::TimeBase;
+use zenjep::flightlistgenerator::{FlightList, FlightListCache, LoadOptions};
+use zenjep::importer::ExportFormat;
 use zenjep::pages::allpages::*;
 use zenjep::renderable::filters::FlightFilter;
-
+use zenjep::renderable::logbook::{Pagination, PilotIdentity};
 
 #[async_std::main]
 async fn main() -> anyhow::Result<()> {
@@ -19,6 +27,7 @@ async fn main() -> anyhow::Result<()> {
                 .short('f')
                 .long("file")
                 .value_name("FILE")
+                .global(true)
                 .help("Input file, yaml logbook"),
         )
         .arg(
@@ -26,7 +35,177 @@ async fn main() -> anyhow::Result<()> {
                 .short('l')
                 .long("loglevel")
                 .value_name("LOGLEVEL")
-                .help("log level: info, warn, trace. Defaults to info"),
+                .global(true)
+                .help("log level: info, warn, trace. Defaults to info, warn for check and import"),
+        )
+        .arg(
+            Arg::new("rule")
+                .short('r')
+                .long("rule")
+                .value_name("RULE=LEVEL")
+                .action(ArgAction::Append)
+                .global(true)
+                .help("severity of a validation rule: error, warning or off. Can be repeated"),
+        )
+        .arg(
+            Arg::new("aircraft")
+                .long("aircraft")
+                .value_name("FILE")
+                .global(true)
+                .help("Aircraft catalog. Defaults to aircraft.yaml next to the logbook"),
+        )
+        .arg(
+            Arg::new("airports")
+                .long("airports")
+                .value_name("FILE")
+                .global(true)
+                .help("Airport catalog. Defaults to airports.yaml next to the logbook"),
+        )
+        .arg(
+            Arg::new("airport-data")
+                .long("airport-data")
+                .value_name("FILE")
+                .global(true)
+                .help("Airport reference data, OurAirports csv. Defaults to ourairports.csv next to the logbook"),
+        )
+        .arg(
+            Arg::new("lines-per-page")
+                .long("lines-per-page")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .global(true)
+                .help("Jeppesen pages of N flights. Without it, pages end at end_of_page"),
+        )
+        .arg(
+            Arg::new("pages-per-book")
+                .long("pages-per-book")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .global(true)
+                .help("Jeppesen books of N pages. Without it, books end at end_of_book"),
+        )
+        .arg(
+            Arg::new("pilot-name")
+                .long("pilot-name")
+                .value_name("NAME")
+                .global(true)
+                .help("Pilot name in the footer of the printed pages"),
+        )
+        .arg(
+            Arg::new("licence")
+                .long("licence")
+                .value_name("NUMBER")
+                .global(true)
+                .help("Licence number in the footer of the printed pages"),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check the logbook and print all the problems found")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .default_value("text")
+                        .help("output format: text or json"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Append the flights of another logbook to the yaml logbook")
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .global(true)
+                        .help("yaml file where the flights are appended, - for stdout. Defaults to the logbook"),
+                )
+                .subcommand(
+                    Command::new("csv")
+                        .about("Import a csv file with a column mapping")
+                        .arg(
+                            Arg::new("mapping")
+                                .short('m')
+                                .long("mapping")
+                                .value_name("FILE")
+                                .required(true)
+                                .help("yaml file mapping the flight keys to the csv columns"),
+                        )
+                        .arg(Arg::new("input").value_name("CSV").required(true)),
+                )
+                .subcommand(
+                    Command::new("mccpilotlog")
+                        .about("Import the csv export of mccPILOTLOG")
+                        .arg(Arg::new("input").value_name("CSV").required(true)),
+                )
+                .subcommand(
+                    Command::new("foreflight")
+                        .alias("logten")
+                        .about("Import the csv export of ForeFlight or LogTen Pro")
+                        .arg(Arg::new("input").value_name("CSV").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export the logbook to a file")
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .global(true)
+                        .help("exported file, - for stdout. Defaults to the logbook with the extension of the format"),
+                )
+                .arg(
+                    Arg::new("time")
+                        .long("time")
+                        .value_name("TIME")
+                        .default_value("utc")
+                        .global(true)
+                        .help("times in utc or in the local time of the airports"),
+                )
+                .arg(
+                    Arg::new("after")
+                        .long("after")
+                        .value_name("YYYY-MM-DD")
+                        .global(true)
+                        .help("only the flights from this date"),
+                )
+                .arg(
+                    Arg::new("before")
+                        .long("before")
+                        .value_name("YYYY-MM-DD")
+                        .global(true)
+                        .help("only the flights until this date"),
+                )
+                .arg(
+                    Arg::new("model")
+                        .long("model")
+                        .value_name("MODEL")
+                        .global(true)
+                        .help("only the flights of this aircraft model"),
+                )
+                .arg(
+                    Arg::new("family")
+                        .long("family")
+                        .value_name("FAMILY")
+                        .global(true)
+                        .help("only the flights of this aircraft family"),
+                )
+                .subcommand(
+                    Command::new("pdf")
+                        .about("The Jeppesen logbook, a left and a right A4 landscape sheet per page"),
+                )
+                .subcommand(
+                    Command::new("csv")
+                        .about("The flights, one per row with the data of their aircraft"),
+                )
+                .subcommand(
+                    Command::new("xlsx")
+                        .about("The flights as a spreadsheet, one per row with the data of their aircraft"),
+                ),
         )
         .get_matches();
 
@@ -40,6 +219,11 @@ async fn main() -> anyhow::Result<()> {
     // fetch the log level from the command line
     let raw_log_level = if let Some(log_level) = matches.get_one::<String>("loglevel") {
         log_level
+    } else if matches!(
+        matches.subcommand_name(),
+        Some("check" | "import" | "export")
+    ) {
+        "warn"
     } else {
         "info"
     };
@@ -56,19 +240,163 @@ async fn main() -> anyhow::Result<()> {
     };
     simple_logger::init_with_level(log_level).unwrap();
 
+    // options of the logbook loader
+    let mut options = LoadOptions {
+        aircraft_catalog_file: matches.get_one::<String>("aircraft").map(PathBuf::from),
+        airport_catalog_file: matches.get_one::<String>("airports").map(PathBuf::from),
+        airport_data_file: matches.get_one::<String>("airport-data").map(PathBuf::from),
+        ..LoadOptions::default()
+    };
+    if let Some(rules) = matches.get_many::<String>("rule") {
+        for rule in rules {
+            options.rules.set_from_str(rule)?;
+        }
+    }
+
+    // layout of the Jeppesen pages and of their exports
+    let pagination = Pagination {
+        lines_per_page: matches
+            .get_one::<u64>("lines-per-page")
+            .map(|n| *n as usize),
+        pages_per_book: matches
+            .get_one::<u64>("pages-per-book")
+            .map(|n| *n as usize),
+    };
+    let pilot = PilotIdentity {
+        name: matches
+            .get_one::<String>("pilot-name")
+            .cloned()
+            .unwrap_or_default(),
+        licence_number: matches
+            .get_one::<String>("licence")
+            .cloned()
+            .unwrap_or_default(),
+    };
+
+    // check command : print the problems and exit
+    if let Some(("check", check_matches)) = matches.subcommand() {
+        let format = CheckFormat::from_name(check_matches.get_one::<String>("format").unwrap())?;
+        if !check_logbook(yaml_file, &options, format)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // import command : append the flights of another logbook and exit
+    if let Some(("import", import_matches)) = matches.subcommand() {
+        let output = import_matches
+            .get_one::<String>("output")
+            .unwrap_or(yaml_file);
+        let imported = match import_matches.subcommand() {
+            Some(("csv", csv_matches)) => import_csv(
+                yaml_file,
+                csv_matches.get_one::<String>("input").unwrap(),
+                csv_matches.get_one::<String>("mapping").unwrap(),
+                output,
+                &options,
+            )?,
+            Some(("mccpilotlog", export_matches)) => import_export_file(
+                yaml_file,
+                export_matches.get_one::<String>("input").unwrap(),
+                ExportFormat::MccPilotLog,
+                output,
+                &options,
+            )?,
+            Some(("foreflight", export_matches)) => import_export_file(
+                yaml_file,
+                export_matches.get_one::<String>("input").unwrap(),
+                ExportFormat::ForeFlight,
+                output,
+                &options,
+            )?,
+            _ => unreachable!("subcommand required"),
+        };
+        if !imported {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // export command : write the logbook in another format and exit
+    if let Some(("export", export_matches)) = matches.subcommand() {
+        let time_base = TimeBase::from_name(export_matches.get_one::<String>("time").unwrap())?;
+        let (format, _) = export_matches.subcommand().expect("subcommand required");
+        let default_output = Path::new(yaml_file).with_extension(format);
+        let output = match export_matches.get_one::<String>("output") {
+            Some(output) => output.clone(),
+            None => default_output.to_string_lossy().into_owned(),
+        };
+        let filter = FlightFilter::from_options(
+            export_matches
+                .get_one::<String>("after")
+                .map(String::as_str),
+            export_matches
+                .get_one::<String>("before")
+                .map(String::as_str),
+            export_matches
+                .get_one::<String>("model")
+                .map(String::as_str),
+            export_matches
+                .get_one::<String>("family")
+                .map(String::as_str),
+        )?;
+        match format {
+            "pdf" => export_pdf(
+                yaml_file, &output, &options, &filter, time_base, pagination, &pilot,
+            )?,
+            "csv" => export_csv(yaml_file, &output, &options, &filter)?,
+            "xlsx" => export_xlsx(yaml_file, &output, &options, &filter)?,
+            _ => unreachable!("subcommand required"),
+        }
+        return Ok(());
+    }
+
+    // check the whole logbook once at startup and log every problem found
+    match FlightList::load_with_diagnostics(yaml_file, &options) {
+        Ok((_, diagnostics)) => {
+            for diagnostic in diagnostics.iter() {
+                match diagnostic.severity {
+                    Severity::Error => log::error!("{diagnostic}"),
+                    Severity::Warning => log::warn!("{diagnostic}"),
+                }
+            }
+        }
+        Err(e) => log::error!("{e:#}"),
+    }
+
     let appstate = AppState {
-        filename: Arc::new(Mutex::new(yaml_file.to_string())),
+        flight_list: Arc::new(Mutex::new(FlightListCache::new(yaml_file, options))),
         filter: Arc::new(Mutex::new(FlightFilter::default())),
+        pagination: Arc::new(Mutex::new(pagination)),
+        pilot: Arc::new(Mutex::new(pilot)),
+        reload_events: Default::default(),
     };
+    appstate
+        .reload_events
+        .clone()
+        .watch(appstate.flight_list.clone());
 
     let mut app = tide::with_state(appstate);
     app.with(tide::log::LogMiddleware::new());
+    app.with(reload_banner);
     app.at("/").get(page_home);
     app.at("/jeppesen").get(page_jeppesen);
+    app.at("/jeppesen/print").get(page_jeppesen_print);
+    app.at("/jeppesen.pdf").get(page_jeppesen_pdf);
     app.at("/years").get(page_years);
     app.at("/aircrafts").get(page_aircrafts);
+    app.at("/distances").get(page_distances);
+    app.at("/flights.csv").get(page_flights_csv);
+    app.at("/flights.xlsx").get(page_flights_xlsx);
     app.at("/familiesandmodels").get(page_families);
     app.at("/filters").get(page_filter);
+    app.at("/events").get(tide::sse::endpoint(page_events));
+    app.at("/api/v1/flights").get(api_flights);
+    app.at("/api/v1/aircrafts").get(api_aircrafts);
+    app.at("/api/v1/aircrafts/totals").get(api_aircraft_totals);
+    app.at("/api/v1/airports").get(api_airports);
+    app.at("/api/v1/jeppesen").get(api_jeppesen);
+    app.at("/api/v1/years").get(api_years);
 
     app.listen("127.0.0.1:2454").await?;
     Ok(())
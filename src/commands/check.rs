@@ -0,0 +1,144 @@
+use crate::flightlistgenerator::diagnostic::{Diagnostic, Diagnostics, Severity};
+use crate::flightlistgenerator::{FlightList, LoadOptions};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::io::Write;
+
+/// Output format of the check command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckFormat {
+    /// one line per problem : file:line:column: severity ...
+    Text,
+    /// an array of objects, for editors
+    Json,
+}
+
+impl CheckFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "text" => Ok(CheckFormat::Text),
+            "json" => Ok(CheckFormat::Json),
+            _ => bail!("unknown format {name}, expected text or json"),
+        }
+    }
+}
+
+/// One problem, as written in the json output
+#[derive(Serialize)]
+pub(crate) struct DiagnosticJson<'a> {
+    file: &'a str,
+    line: Option<usize>,
+    column: Option<usize>,
+    severity: String,
+    message: &'a str,
+    flight_index: Option<usize>,
+}
+
+impl<'a> From<&'a Diagnostic> for DiagnosticJson<'a> {
+    fn from(diagnostic: &'a Diagnostic) -> Self {
+        DiagnosticJson {
+            file: &diagnostic.file,
+            line: diagnostic.position.map(|position| position.line),
+            column: diagnostic.position.map(|position| position.column),
+            severity: diagnostic.severity.to_string(),
+            message: &diagnostic.message,
+            flight_index: diagnostic.flight_index,
+        }
+    }
+}
+
+/// Number of problems found in a logbook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckCounts {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// Load the logbook, print all the problems found on stdout.
+/// Returns true if the logbook has no error, warnings are accepted.
+/// The check command exits with status 1 when false.
+pub fn check_logbook(yaml_file: &str, options: &LoadOptions, format: CheckFormat) -> Result<bool> {
+    let counts = check_report(yaml_file, options, format, &mut std::io::stdout().lock())?;
+    if format == CheckFormat::Text {
+        eprintln!(
+            "{yaml_file} : {} error(s), {} warning(s)",
+            counts.errors, counts.warnings
+        );
+    }
+    Ok(counts.errors == 0)
+}
+
+/// Load the logbook, write all the problems found in the output
+pub fn check_report(
+    yaml_file: &str,
+    options: &LoadOptions,
+    format: CheckFormat,
+    output: &mut impl Write,
+) -> Result<CheckCounts> {
+    let (_, diagnostics) = FlightList::load_with_diagnostics(yaml_file, options)?;
+    write_diagnostics(&diagnostics, format, output)?;
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    Ok(CheckCounts {
+        errors,
+        warnings: diagnostics.iter().count() - errors,
+    })
+}
+
+/// Write the problems in the format of the check command
+fn write_diagnostics(
+    diagnostics: &Diagnostics,
+    format: CheckFormat,
+    output: &mut impl Write,
+) -> Result<()> {
+    match format {
+        CheckFormat::Text => {
+            for diagnostic in diagnostics.iter() {
+                writeln!(output, "{diagnostic}")?;
+            }
+        }
+        CheckFormat::Json => {
+            let output_json: Vec<DiagnosticJson> =
+                diagnostics.iter().map(DiagnosticJson::from).collect();
+            writeln!(output, "{}", serde_json::to_string_pretty(&output_json)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn check_output() {
+    let samples = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests");
+    let options = LoadOptions::default();
+    let errors = format!("{samples}/errors.yaml");
+    let mut text = Vec::new();
+    let counts = check_report(&errors, &options, CheckFormat::Text, &mut text).unwrap();
+    assert_eq!(
+        counts,
+        CheckCounts {
+            errors: 2,
+            warnings: 0
+        }
+    );
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.starts_with(&format!(
+        "{errors}:30:3: error in flight number 1 (2001-01-02 05:00) : unknown key [oc_time_nigth], did you mean [oc_time_night] ?"
+    )));
+    assert_eq!(text.lines().count(), 2);
+
+    let mut text = Vec::new();
+    let logbook = format!("{samples}/logbook.yaml");
+    let counts = check_report(&logbook, &options, CheckFormat::Text, &mut text).unwrap();
+    assert_eq!(counts.errors, 0);
+    assert!(text.is_empty());
+
+    let mut json = Vec::new();
+    check_report(&errors, &options, CheckFormat::Json, &mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json[1]["line"], 34);
+    assert_eq!(json[1]["severity"], "error");
+    assert_eq!(json[1]["flight_index"], 2);
+    assert_eq!(json[1]["file"], errors.as_str());
+}
@@ -0,0 +1,73 @@
+use std::fs::write;
+use std::io::Write;
+
+use anyhow::{bail, Context, Result};
+
+use crate::exporter::flighttable::{flights_csv, flights_xlsx};
+use crate::exporter::pdf::jeppesen_pdf;
+use crate::flightlistgenerator::flighttime::TimeBase;
+use crate::flightlistgenerator::{FlightList, LoadOptions};
+use crate::renderable::filters::FlightFilter;
+use crate::renderable::logbook::{JeppesenBookShelfHtml, Pagination, PilotIdentity};
+
+/// Write the Jeppesen logbook as a PDF to output ("-" for stdout),
+/// paginated like the Jeppesen page.
+pub fn export_pdf(
+    logbook: &str,
+    output: &str,
+    options: &LoadOptions,
+    filter: &FlightFilter,
+    time_base: TimeBase,
+    pagination: Pagination,
+    pilot: &PilotIdentity,
+) -> Result<()> {
+    let flight_list = load_logbook(logbook, options, filter)?;
+    let shelf = JeppesenBookShelfHtml::build_from(&flight_list, time_base, pagination);
+    write_output(output, &jeppesen_pdf(&shelf, pilot))
+}
+
+/// Write the flights, one per row with the data of their aircraft,
+/// as csv to output ("-" for stdout)
+pub fn export_csv(
+    logbook: &str,
+    output: &str,
+    options: &LoadOptions,
+    filter: &FlightFilter,
+) -> Result<()> {
+    let flight_list = load_logbook(logbook, options, filter)?;
+    write_output(output, &flights_csv(&flight_list)?)
+}
+
+/// Write the flights, one per row with the data of their aircraft,
+/// as an xlsx spreadsheet to output ("-" for stdout)
+pub fn export_xlsx(
+    logbook: &str,
+    output: &str,
+    options: &LoadOptions,
+    filter: &FlightFilter,
+) -> Result<()> {
+    let flight_list = load_logbook(logbook, options, filter)?;
+    write_output(output, &flights_xlsx(&flight_list)?)
+}
+
+/// The filtered flight list of the logbook, which must have no error
+fn load_logbook(logbook: &str, options: &LoadOptions, filter: &FlightFilter) -> Result<FlightList> {
+    let (mut flight_list, diagnostics) = FlightList::load_with_diagnostics(logbook, options)?;
+    if diagnostics.has_errors() {
+        bail!("Errors in the logbook {logbook}, run the check command first :\n{diagnostics}");
+    }
+    flight_list.filter(filter);
+    Ok(flight_list)
+}
+
+fn write_output(output: &str, content: &[u8]) -> Result<()> {
+    if output == "-" {
+        std::io::stdout()
+            .write_all(content)
+            .context("Cannot write to stdout")?;
+    } else {
+        write(output, content).with_context(|| format!("Cannot write file : {output}"))?;
+        eprintln!("{output} written");
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use strict_yaml_rust::{StrictYaml, StrictYamlLoader};
+
+use crate::flightlistgenerator::diagnostic::{Diagnostics, Severity};
+use crate::flightlistgenerator::{FlightList, LoadOptions};
+use crate::importer::csvmapping::CsvMapping;
+use crate::importer::{format_flights, import_flights, ExportFormat, ImportedFlight};
+
+/// Import the flights of a csv file, see CsvMapping for the mapping file.
+/// Returns true if the flights have been imported.
+pub fn import_csv(
+    logbook: &str,
+    csv_file: &str,
+    mapping_file: &str,
+    output: &str,
+    options: &LoadOptions,
+) -> Result<bool> {
+    let mapping = CsvMapping::from_yaml_file(mapping_file)?;
+    let mut diagnostics = Diagnostics::default();
+    let flights = mapping.read_flights(csv_file, &mut diagnostics)?;
+    import(logbook, csv_file, &flights, diagnostics, output, options)
+}
+
+/// Import the flights of the export file of another logbook tool.
+/// Returns true if the flights have been imported.
+pub fn import_export_file(
+    logbook: &str,
+    export_file: &str,
+    format: ExportFormat,
+    output: &str,
+    options: &LoadOptions,
+) -> Result<bool> {
+    let mut diagnostics = Diagnostics::default();
+    let flights = format.read_flights(export_file, &mut diagnostics)?;
+    import(logbook, export_file, &flights, diagnostics, output, options)
+}
+
+/// Check the imported flights as if they were at the end of the logbook,
+/// print the problems found, and append the flights to output
+/// ("-" for stdout). Nothing is written if a flight has an error.
+fn import(
+    logbook: &str,
+    imported_file: &str,
+    imported_flights: &[ImportedFlight],
+    mut diagnostics: Diagnostics,
+    output: &str,
+    options: &LoadOptions,
+) -> Result<bool> {
+    // the aircraft and airports already defined in the logbook
+    let (mut flight_list, logbook_diagnostics) = if Path::new(logbook).exists() {
+        FlightList::load_with_diagnostics(logbook, options)?
+    } else {
+        FlightList::with_catalogs(logbook, options)?
+    };
+    if logbook_diagnostics.has_errors() {
+        bail!(
+            "Errors in the logbook {logbook}, run the check command first :\n{logbook_diagnostics}"
+        );
+    }
+    let flights = import_flights(
+        imported_flights,
+        imported_file,
+        &mut flight_list,
+        options,
+        &mut diagnostics,
+    );
+
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{diagnostic}");
+    }
+    let nb_errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let nb_warnings = diagnostics.iter().count() - nb_errors;
+    eprintln!(
+        "{imported_file} : {} flight(s), {nb_errors} error(s), {nb_warnings} warning(s)",
+        imported_flights.len()
+    );
+    if diagnostics.has_errors() {
+        eprintln!("Nothing imported");
+        return Ok(false);
+    }
+
+    let formatted_flights = format_flights(&flights);
+    if output == "-" {
+        print!("{formatted_flights}");
+    } else {
+        append_flights(output, &formatted_flights)?;
+        eprintln!("{} flight(s) appended to {output}", flights.len());
+    }
+    Ok(true)
+}
+
+/// Append formatted flights to a yaml vector of flights
+fn append_flights(output: &str, formatted_flights: &str) -> Result<()> {
+    let existing = if Path::new(output).exists() {
+        read_to_string(output).with_context(|| format!("Cannot open file : {output}"))?
+    } else {
+        String::new()
+    };
+    if let Ok(documents) = StrictYamlLoader::load_from_str(&existing) {
+        if let Some(StrictYaml::Hash(_)) = documents.first() {
+            bail!("{output} is a manifest, the flights must go to one of its included files");
+        }
+    }
+    let separator = match existing.trim_end_matches(' ') {
+        "" => "",
+        content if content.ends_with("\n\n") => "",
+        content if content.ends_with('\n') => "\n",
+        _ => "\n\n",
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(output)
+        .with_context(|| format!("Cannot write file : {output}"))?;
+    write!(file, "{separator}{formatted_flights}")
+        .with_context(|| format!("Cannot write file : {output}"))?;
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! The flight list as a table, one row per flight with the data of its
+//! aircraft, written as csv or as an xlsx spreadsheet.
+//!
+//! The columns are named after the yaml keys. The dates are in UTC,
+//! written 2023-03-26 14:05 like in the logbook.
+use anyhow::{Context, Result};
+use rust_xlsxwriter::{Format, Workbook};
+use time::macros::datetime;
+use time::PrimitiveDateTime;
+
+use crate::flightlistgenerator::flight::Flight;
+use crate::flightlistgenerator::flighttime::FlightTime;
+use crate::flightlistgenerator::FlightList;
+
+/// Columns of the table, in the order of the values of a row
+pub const COLUMNS: &[&str] = &[
+    "date_start",
+    "date_end",
+    "date_takeoff",
+    "date_landing",
+    "is_sim",
+    "apt_departure",
+    "apt_arrival",
+    "immatriculation",
+    "acmodel",
+    "actype",
+    "nb_engines",
+    "mtow",
+    "multipilot",
+    "cat1",
+    "cat2",
+    "cat3",
+    "single_pilot_time_se",
+    "single_pilot_time_me",
+    "multi_pilot_time",
+    "duration_total",
+    "airborne_time",
+    "pic",
+    "takeoff_day",
+    "takeoff_night",
+    "landing_day",
+    "landing_night",
+    "oc_time_ifr",
+    "oc_time_night",
+    "computed_night_time",
+    "duration_pic",
+    "copilot_time",
+    "dual_time",
+    "instructor_time",
+    "sim_type",
+    "sim_total_time",
+    "distance_nm",
+    "comment",
+    "end_of_page",
+    "end_of_book",
+    "file",
+    "line",
+];
+
+/// A cell of the table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Text(String),
+    Integer(u32),
+    Boolean(bool),
+    Duration(FlightTime),
+    DateTime(PrimitiveDateTime),
+}
+
+impl Value {
+    fn optional_datetime(date: Option<PrimitiveDateTime>) -> Self {
+        date.map_or(Value::Empty, Value::DateTime)
+    }
+
+    fn optional_duration(duration: Option<FlightTime>) -> Self {
+        duration.map_or(Value::Empty, Value::Duration)
+    }
+
+    fn optional_integer(integer: Option<u32>) -> Self {
+        integer.map_or(Value::Empty, Value::Integer)
+    }
+
+    /// The text of a csv cell, a duration is H:MM, 0:00 when zero
+    fn to_csv(&self) -> String {
+        match self {
+            Value::Empty => String::new(),
+            Value::Text(text) => text.clone(),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Boolean(boolean) => boolean.to_string(),
+            Value::Duration(duration) => {
+                let minutes = duration.0.whole_minutes();
+                format!("{}:{:02}", minutes / 60, minutes % 60)
+            }
+            Value::DateTime(date) => format!(
+                "{}-{:02}-{:02} {:02}:{:02}",
+                date.year(),
+                date.month() as u8,
+                date.day(),
+                date.hour(),
+                date.minute()
+            ),
+        }
+    }
+}
+
+/// The rows of the flights of the list, in the order of COLUMNS.
+/// The aircraft columns are empty for the simulator sessions.
+pub fn flight_rows(flight_list: &FlightList) -> Vec<Vec<Value>> {
+    flight_list
+        .flights
+        .iter()
+        .map(|flight| flight_row(flight_list, flight))
+        .collect()
+}
+
+fn flight_row(flight_list: &FlightList, flight: &Flight) -> Vec<Value> {
+    let text = |text: &str| {
+        if text.is_empty() {
+            Value::Empty
+        } else {
+            Value::Text(text.to_string())
+        }
+    };
+    let single_pilot_time = |single_pilot: bool| {
+        Value::Duration(if single_pilot {
+            flight.total_flight_time
+        } else {
+            FlightTime::ZERO
+        })
+    };
+    let aircrafts = &flight_list.aircrafts;
+    let (family, model) = if flight.is_sim {
+        (None, None)
+    } else {
+        (
+            aircrafts.get_family_name(&flight.immatriculation).ok(),
+            aircrafts.get_model(&flight.immatriculation).ok(),
+        )
+    };
+    let mut row = vec![
+        Value::DateTime(flight.date_start),
+        Value::DateTime(flight.date_end),
+        Value::optional_datetime(flight.date_takeoff),
+        Value::optional_datetime(flight.date_landing),
+        Value::Boolean(flight.is_sim),
+        text(&flight.airport_departure),
+        text(&flight.airport_arrival),
+        text(&flight.immatriculation),
+        text(&flight.acmodel),
+        family.map_or(Value::Empty, text),
+    ];
+    match model {
+        Some(model) => row.extend([
+            Value::Integer(model.nb_engines as u32),
+            Value::Integer(model.mtow),
+            Value::Boolean(model.multipilot),
+            text(&model.category_1),
+            text(&model.category_2),
+            text(&model.category_3),
+        ]),
+        None => row.extend((0..6).map(|_| Value::Empty)),
+    }
+    row.extend([
+        single_pilot_time(flight.single_pilot_time_se),
+        single_pilot_time(flight.single_pilot_time_me),
+        Value::Duration(flight.multi_pilot_time),
+        Value::Duration(flight.total_flight_time),
+        Value::optional_duration(flight.airborne_time),
+        text(&flight.name_pic),
+        Value::Integer(flight.takeoff_day as u32),
+        Value::Integer(flight.takeoff_night as u32),
+        Value::Integer(flight.landing_day as u32),
+        Value::Integer(flight.landing_night as u32),
+        Value::Duration(flight.operational_condition_time_ifr),
+        Value::Duration(flight.operational_condition_time_night),
+        Value::optional_duration(flight.computed_night_time),
+        Value::Duration(flight.pilot_in_command_time),
+        Value::Duration(flight.copilot_time),
+        Value::Duration(flight.dual_time),
+        Value::Duration(flight.instructor_time),
+        text(&flight.sim_type),
+        Value::Duration(flight.sim_total_time_of_session),
+        Value::optional_integer(flight.distance_nm),
+        text(&flight.remark),
+        Value::Boolean(flight.end_of_page),
+        Value::Boolean(flight.end_of_book),
+        text(&flight.source.file),
+        Value::optional_integer(flight.source.position.map(|position| position.line as u32)),
+    ]);
+    row
+}
+
+/// The flights as csv, with a header line
+pub fn flights_csv(flight_list: &FlightList) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(COLUMNS)?;
+    for row in flight_rows(flight_list) {
+        writer.write_record(row.iter().map(Value::to_csv))?;
+    }
+    writer.into_inner().context("Cannot write the csv")
+}
+
+/// The flights as an xlsx workbook, with a header line.
+/// The durations and dates are numbers formatted as such.
+pub fn flights_xlsx(flight_list: &FlightList) -> Result<Vec<u8>> {
+    // the serial numbers of the dates count the days from 1899-12-30
+    const EPOCH: PrimitiveDateTime = datetime!(1899-12-30 00:00);
+    let header_format = Format::new().set_bold();
+    let duration_format = Format::new().set_num_format("[h]:mm");
+    let date_format = Format::new().set_num_format("yyyy-mm-dd hh:mm");
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("flights")?;
+    for (column, name) in COLUMNS.iter().enumerate() {
+        worksheet.write_string_with_format(0, column as u16, *name, &header_format)?;
+    }
+    let rows = flight_rows(flight_list);
+    for (index, row) in rows.iter().enumerate() {
+        let line = index as u32 + 1;
+        for (column, value) in row.iter().enumerate() {
+            let column = column as u16;
+            match value {
+                Value::Empty => {}
+                Value::Text(text) => {
+                    worksheet.write_string(line, column, text)?;
+                }
+                Value::Integer(integer) => {
+                    worksheet.write_number(line, column, *integer)?;
+                }
+                Value::Boolean(boolean) => {
+                    worksheet.write_boolean(line, column, *boolean)?;
+                }
+                Value::Duration(duration) => {
+                    let days = duration.0.whole_minutes() as f64 / (24.0 * 60.0);
+                    worksheet.write_number_with_format(line, column, days, &duration_format)?;
+                }
+                Value::DateTime(date) => {
+                    let days = (*date - EPOCH).as_seconds_f64() / (24.0 * 3600.0);
+                    worksheet.write_number_with_format(line, column, days, &date_format)?;
+                }
+            }
+        }
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+    worksheet.autofilter(0, 0, rows.len() as u32, COLUMNS.len() as u16 - 1)?;
+    worksheet.autofit();
+    workbook
+        .save_to_buffer()
+        .context("Cannot write the xlsx workbook")
+}
+
+#[test]
+fn flight_table() {
+    let flight_list = FlightList::load_from_yaml(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/logbook.yaml"
+    ))
+    .unwrap();
+    let rows = flight_rows(&flight_list);
+    assert_eq!(rows.len(), 4);
+    assert!(rows.iter().all(|row| row.len() == COLUMNS.len()));
+    let column = |name: &str| COLUMNS.iter().position(|column| *column == name).unwrap();
+    assert_eq!(rows[0][column("acmodel")], Value::Text("C172".to_string()));
+    assert_eq!(rows[0][column("nb_engines")], Value::Integer(1));
+
+    let csv = String::from_utf8(flights_csv(&flight_list).unwrap()).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("date_start,date_end,"));
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("2001-01-01 05:00,2001-01-01 06:00,"));
+    assert!(flights_xlsx(&flight_list).unwrap().starts_with(b"PK"));
+}
@@ -0,0 +1,3 @@
+//! Export the logbook to files for other uses than the web pages.
+pub mod flighttable;
+pub mod pdf;
@@ -0,0 +1,644 @@
+//! The Jeppesen logbook as a PDF, laid out like the print page :
+//! each page of the logbook is a left and a right A4 landscape sheet,
+//! with the page totals, the totals brought forward and the overall totals.
+//! A page with more lines than a sheet holds goes on over the next sheets.
+//!
+//! The text uses the standard Helvetica fonts of the PDF readers,
+//! nothing is embedded.
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::flightlistgenerator::flight::Flight;
+use crate::renderable::logbook::{
+    JeppesenBookShelfHtml, JeppesenPage, PageSheet, PilotIdentity, TotalLine,
+};
+
+/// A4 landscape, in points
+const SHEET_WIDTH: f32 = 842.0;
+const SHEET_HEIGHT: f32 = 595.0;
+/// 8 mm, like the print page
+const MARGIN: f32 = 22.7;
+/// Height of the line above and below the table of a sheet
+const BANNER_HEIGHT: f32 = 14.0;
+/// Heights of the header rows : column numbers, titles, subtitles
+const HEADER_HEIGHTS: [f32; 3] = [10.0, 16.0, 16.0];
+/// Height of the flight and total rows, lower when a page has many flights,
+/// down to the rows of a paper logbook
+const MAX_ROW_HEIGHT: f32 = 18.0;
+const MIN_ROW_HEIGHT: f32 = 12.0;
+const MAX_FONT_SIZE: f32 = 7.0;
+const HEADER_FONT_SIZE: f32 = 5.0;
+const CELL_PADDING: f32 = 1.5;
+const TOTAL_GRAY: f32 = 0.93;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// A column of a sheet. The consecutive columns with the same number
+/// share the number and the title, the subtitle is their own.
+/// width : relative to the other columns of the sheet
+struct Column {
+    number: &'static str,
+    title: &'static str,
+    subtitle: &'static str,
+    width: f32,
+}
+
+const fn column(
+    number: &'static str,
+    title: &'static str,
+    subtitle: &'static str,
+    width: f32,
+) -> Column {
+    Column {
+        number,
+        title,
+        subtitle,
+        width,
+    }
+}
+
+/// Columns 1 to 9 of the EASA logbook
+const LEFT_COLUMNS: &[Column] = &[
+    column("1", "DATE (dd/mm/yyyy)", "", 1.3),
+    column("2", "DEPARTURE", "PLACE", 1.0),
+    column("2", "DEPARTURE", "TIME", 0.8),
+    column("3", "ARRIVAL", "PLACE", 1.0),
+    column("3", "ARRIVAL", "TIME", 0.8),
+    column("4", "AIRCRAFT", "MAKE, MODEL, VARIANT", 1.6),
+    column("4", "AIRCRAFT", "REGISTRATION", 1.4),
+    column("5", "SINGLE PILOT TIME", "SE", 1.0),
+    column("5", "SINGLE PILOT TIME", "ME", 1.0),
+    column("6", "MULTI-PILOT TIME", "", 1.0),
+    column("7", "TOTAL TIME OF FLIGHT", "", 1.0),
+    column("8", "NAME PIC", "", 1.8),
+    column("9", "LANDINGS", "DAY", 0.7),
+    column("9", "LANDINGS", "NIGHT", 0.7),
+];
+
+/// Columns of the left sheet covered by the label of the total rows
+const LEFT_LABEL_SPAN: usize = 7;
+
+/// Columns 10 to 13 of the EASA logbook
+const RIGHT_COLUMNS: &[Column] = &[
+    column("10", "OPERATIONAL CONDITION TIME", "NIGHT", 1.0),
+    column("10", "OPERATIONAL CONDITION TIME", "IFR", 1.0),
+    column("11", "PILOT FUNCTION TIME", "PILOT-IN-COMMAND", 1.1),
+    column("11", "PILOT FUNCTION TIME", "CO-PILOT", 1.0),
+    column("11", "PILOT FUNCTION TIME", "DUAL", 1.0),
+    column("11", "PILOT FUNCTION TIME", "INSTRUCTOR", 1.1),
+    column("12", "FSTD SESSION", "DATE (dd/mm/yyyy)", 1.3),
+    column("12", "FSTD SESSION", "TYPE", 1.2),
+    column("12", "FSTD SESSION", "TOTAL TIME OF SESSION", 1.0),
+    column("13", "REMARKS AND ENDORSEMENTS", "", 5.0),
+];
+
+/// Labels of the three total rows at the end of a page
+const TOTAL_LABELS: [&str; 3] = ["TOTAL THIS PAGE", "TOTAL FROM PREVIOUS PAGES", "TOTAL TIME"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+}
+
+/// A cell of a flight or total row, over span columns
+struct Cell {
+    text: String,
+    span: usize,
+    align: Align,
+}
+
+impl Cell {
+    fn new(text: impl ToString) -> Self {
+        Cell {
+            text: text.to_string(),
+            span: 1,
+            align: Align::Center,
+        }
+    }
+
+    fn empty() -> Self {
+        Cell::new("")
+    }
+}
+
+/// Render the bookshelf into a PDF document, two sheets per page,
+/// or more for a long page.
+/// An empty logbook gives the sheets of an empty first page.
+pub fn jeppesen_pdf(shelf: &JeppesenBookShelfHtml, pilot: &PilotIdentity) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let info_id = Ref::new(5);
+    let mut next_id = 6;
+
+    let empty_page = JeppesenPage {
+        page_number: 1,
+        book_number: 1,
+        ..JeppesenPage::default()
+    };
+    let mut pages: Vec<&JeppesenPage> = shelf
+        .bookshelf
+        .iter()
+        .flat_map(|book| book.pages.iter())
+        .collect();
+    if pages.is_empty() {
+        pages.push(&empty_page);
+    }
+
+    let mut pdf = Pdf::new();
+    let mut sheet_ids = Vec::new();
+    let sheets = pages.into_iter().flat_map(|page| {
+        page.sheets(rows_per_sheet())
+            .into_iter()
+            .map(move |sheet| (page, sheet))
+    });
+    for (page, sheet) in sheets {
+        for content in [
+            left_sheet(page, &sheet, pilot),
+            right_sheet(page, &sheet, pilot),
+        ] {
+            let sheet_id = Ref::new(next_id);
+            let content_id = Ref::new(next_id + 1);
+            next_id += 2;
+            let mut sheet = pdf.page(sheet_id);
+            sheet
+                .media_box(Rect::new(0.0, 0.0, SHEET_WIDTH, SHEET_HEIGHT))
+                .parent(page_tree_id)
+                .contents(content_id);
+            sheet
+                .resources()
+                .fonts()
+                .pair(REGULAR, regular_id)
+                .pair(BOLD, bold_id);
+            sheet.finish();
+            pdf.stream(content_id, &content.finish());
+            sheet_ids.push(sheet_id);
+        }
+    }
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .count(sheet_ids.len() as i32)
+        .kids(sheet_ids);
+    pdf.type1_font(regular_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    let mut info = pdf.document_info(info_id);
+    info.title(TextStr("Pilot logbook"));
+    if !pilot.name.is_empty() {
+        info.author(TextStr(&pilot.name));
+    }
+    info.finish();
+    pdf.finish()
+}
+
+/// Columns 1 to 9 : the flight, the aircraft, the times and the landings
+fn left_sheet(page: &JeppesenPage, sheet: &PageSheet, pilot: &PilotIdentity) -> Content {
+    let mut rows = Vec::new();
+    for line in sheet.lines {
+        rows.push(left_line(line));
+    }
+    if sheet.with_totals() {
+        let totals = [
+            &page.total_this_page,
+            &page.total_from_previous_pages,
+            &page.total_overall,
+        ];
+        for (label, total) in TOTAL_LABELS.iter().zip(totals) {
+            rows.push(left_total(label, total));
+        }
+    }
+
+    let mut content = Content::new();
+    banner(&mut content, page, sheet, &pilot.name);
+    draw_table(&mut content, LEFT_COLUMNS, &rows, sheet.lines.len());
+    content
+}
+
+/// Columns 10 to 13 : the conditions, the functions, the simulator
+/// and the remarks, with the certification of the pilot
+fn right_sheet(page: &JeppesenPage, sheet: &PageSheet, pilot: &PilotIdentity) -> Content {
+    let mut rows = Vec::new();
+    for line in sheet.lines {
+        rows.push(right_line(line));
+    }
+    let mut content = Content::new();
+    banner(&mut content, page, sheet, "");
+    if !sheet.with_totals() {
+        draw_table(&mut content, RIGHT_COLUMNS, &rows, sheet.lines.len());
+        return content;
+    }
+
+    let totals = [
+        &page.total_this_page,
+        &page.total_from_previous_pages,
+        &page.total_overall,
+    ];
+    for total in totals {
+        rows.push(right_total(total));
+    }
+    let layout = draw_table(&mut content, RIGHT_COLUMNS, &rows, sheet.lines.len());
+    // the remarks column of the total rows
+    let remark_index = RIGHT_COLUMNS.len() - 1;
+    let x = layout.edges[remark_index];
+    let width = layout.edges[remark_index + 1] - x;
+    let height = layout.row_height * TOTAL_LABELS.len() as f32;
+    content
+        .rect(x, layout.totals_top - height, width, height)
+        .stroke();
+    let certification = [
+        "I certify that the entries in this log are true.".to_string(),
+        format!("Pilot's name : {}", pilot.name),
+        format!("Licence number : {}", pilot.licence_number),
+        "Pilot's signature :".to_string(),
+    ];
+    let size = (height / certification.len() as f32 / 1.2).min(MAX_FONT_SIZE - 1.0);
+    for (index, text) in certification.iter().enumerate() {
+        let y = layout.totals_top - (index + 1) as f32 * size * 1.2;
+        show(&mut content, REGULAR, size, x + CELL_PADDING, y, text);
+    }
+    content
+}
+
+fn left_line(line: &Flight) -> Vec<Cell> {
+    if line.is_sim {
+        let mut cells = vec![Cell::new(line.date)];
+        cells.extend((1..LEFT_COLUMNS.len()).map(|_| Cell::empty()));
+        return cells;
+    }
+    let if_positive = |count: u8| {
+        if count > 0 {
+            Cell::new(count)
+        } else {
+            Cell::empty()
+        }
+    };
+    vec![
+        Cell::new(line.date),
+        Cell::new(&line.airport_departure),
+        Cell::new(&line.time_departure),
+        Cell::new(&line.airport_arrival),
+        Cell::new(&line.time_arrival),
+        Cell::new(&line.acmodel),
+        Cell::new(&line.immatriculation),
+        if line.single_pilot_time_se {
+            Cell::new(line.total_flight_time)
+        } else {
+            Cell::empty()
+        },
+        if line.single_pilot_time_me {
+            Cell::new(line.total_flight_time)
+        } else {
+            Cell::empty()
+        },
+        Cell::new(line.multi_pilot_time),
+        Cell::new(line.total_flight_time),
+        Cell::new(&line.name_pic),
+        if_positive(line.landing_day),
+        if_positive(line.landing_night),
+    ]
+}
+
+fn left_total(label: &str, total: &TotalLine) -> Vec<Cell> {
+    vec![
+        Cell {
+            span: LEFT_LABEL_SPAN,
+            ..Cell::new(label)
+        },
+        Cell::new(total.single_pilot_time_se),
+        Cell::new(total.single_pilot_time_me),
+        Cell::new(total.multi_pilot_time),
+        Cell::new(total.total_flight_time),
+        Cell::empty(),
+        Cell::new(total.landing_day),
+        Cell::new(total.landing_night),
+    ]
+}
+
+fn right_line(line: &Flight) -> Vec<Cell> {
+    vec![
+        Cell::new(line.operational_condition_time_night),
+        Cell::new(line.operational_condition_time_ifr),
+        Cell::new(line.pilot_in_command_time),
+        Cell::new(line.copilot_time),
+        Cell::new(line.dual_time),
+        Cell::new(line.instructor_time),
+        if line.is_sim {
+            Cell::new(line.date)
+        } else {
+            Cell::empty()
+        },
+        Cell::new(&line.sim_type),
+        Cell::new(line.sim_total_time_of_session),
+        Cell {
+            align: Align::Left,
+            ..Cell::new(&line.remark)
+        },
+    ]
+}
+
+/// The remarks column is left to the certification
+fn right_total(total: &TotalLine) -> Vec<Cell> {
+    vec![
+        Cell::new(total.operational_condition_time_night),
+        Cell::new(total.operational_condition_time_ifr),
+        Cell::new(total.pilot_in_command_time),
+        Cell::new(total.copilot_time),
+        Cell::new(total.dual_time),
+        Cell::new(total.instructor_time),
+        Cell::empty(),
+        Cell::empty(),
+        Cell::new(total.sim_total_time_of_session),
+    ]
+}
+
+/// The name above the table, the book and page numbers
+fn banner(content: &mut Content, page: &JeppesenPage, sheet: &PageSheet, name: &str) {
+    let y = SHEET_HEIGHT - MARGIN - MAX_FONT_SIZE;
+    show(content, REGULAR, MAX_FONT_SIZE, MARGIN, y, name);
+    let book = format!("book {}", page.book_number);
+    let x = SHEET_WIDTH - MARGIN - text_width(&book, MAX_FONT_SIZE);
+    show(content, REGULAR, MAX_FONT_SIZE, x, y, &book);
+    let number = if sheet.count > 1 {
+        format!(
+            "page {}, sheet {}/{}",
+            page.page_number, sheet.number, sheet.count
+        )
+    } else {
+        format!("page {}", page.page_number)
+    };
+    let x = SHEET_WIDTH - MARGIN - text_width(&number, MAX_FONT_SIZE);
+    show(content, REGULAR, MAX_FONT_SIZE, x, MARGIN, &number);
+}
+
+/// Flight and total rows of a sheet at MIN_ROW_HEIGHT
+fn rows_per_sheet() -> usize {
+    let header_height: f32 = HEADER_HEIGHTS.iter().sum();
+    let available = SHEET_HEIGHT - 2.0 * (MARGIN + BANNER_HEIGHT) - header_height;
+    (available / MIN_ROW_HEIGHT) as usize
+}
+
+/// Position of the table drawn on a sheet
+struct TableLayout {
+    /// left edge of each column, then the right edge of the table
+    edges: Vec<f32>,
+    row_height: f32,
+    /// top of the first total row
+    totals_top: f32,
+}
+
+/// Draw the header and the rows of a sheet, the last rows are the totals.
+/// The rows share the height left by the header, up to MAX_ROW_HEIGHT,
+/// there are at most rows_per_sheet() of them.
+fn draw_table(
+    content: &mut Content,
+    columns: &[Column],
+    rows: &[Vec<Cell>],
+    nb_flight_lines: usize,
+) -> TableLayout {
+    let total_width: f32 = columns.iter().map(|column| column.width).sum();
+    let scale = (SHEET_WIDTH - 2.0 * MARGIN) / total_width;
+    let mut edges = vec![MARGIN];
+    for column in columns {
+        edges.push(edges[edges.len() - 1] + column.width * scale);
+    }
+
+    content.set_line_width(0.5);
+    let top = SHEET_HEIGHT - MARGIN - BANNER_HEIGHT;
+    draw_header(content, columns, &edges, top);
+
+    let header_height: f32 = HEADER_HEIGHTS.iter().sum();
+    let available = top - header_height - MARGIN - BANNER_HEIGHT;
+    let row_height = (available / rows.len() as f32).min(MAX_ROW_HEIGHT);
+    let size = (row_height * 0.6).min(MAX_FONT_SIZE);
+    let mut y = top - header_height;
+    for (index, row) in rows.iter().enumerate() {
+        let total = index >= nb_flight_lines;
+        let font = if total { BOLD } else { REGULAR };
+        y -= row_height;
+        let mut column_index = 0;
+        for cell in row {
+            let x = edges[column_index];
+            let width = edges[column_index + cell.span] - x;
+            if total {
+                content
+                    .set_fill_gray(TOTAL_GRAY)
+                    .rect(x, y, width, row_height)
+                    .fill_nonzero()
+                    .set_fill_gray(0.0);
+            }
+            content.rect(x, y, width, row_height).stroke();
+            let text = fit(&cell.text, width - 2.0 * CELL_PADDING, size);
+            let text_x = match cell.align {
+                Align::Left => x + CELL_PADDING,
+                Align::Center => x + (width - text_width(&text, size)) / 2.0,
+            };
+            let text_y = y + (row_height - size * 0.7) / 2.0;
+            show(content, font, size, text_x, text_y, &text);
+            column_index += cell.span;
+        }
+    }
+    TableLayout {
+        edges,
+        row_height,
+        totals_top: top - header_height - nb_flight_lines as f32 * row_height,
+    }
+}
+
+/// The numbers, titles and subtitles of the columns.
+/// A column alone without subtitle has its title over two rows.
+fn draw_header(content: &mut Content, columns: &[Column], edges: &[f32], top: f32) {
+    let [numbers_height, titles_height, subtitles_height] = HEADER_HEIGHTS;
+    let mut start = 0;
+    while start < columns.len() {
+        let number = columns[start].number;
+        let end = start
+            + columns[start..]
+                .iter()
+                .take_while(|column| column.number == number)
+                .count();
+        let x = edges[start];
+        let width = edges[end] - x;
+        let mut y = top - numbers_height;
+        header_cell(content, x, y, width, numbers_height, number, BOLD);
+        if end - start == 1 && columns[start].subtitle.is_empty() {
+            y -= titles_height + subtitles_height;
+            let height = titles_height + subtitles_height;
+            header_cell(content, x, y, width, height, columns[start].title, REGULAR);
+        } else {
+            y -= titles_height;
+            header_cell(
+                content,
+                x,
+                y,
+                width,
+                titles_height,
+                columns[start].title,
+                REGULAR,
+            );
+            y -= subtitles_height;
+            for index in start..end {
+                let width = edges[index + 1] - edges[index];
+                let subtitle = columns[index].subtitle;
+                header_cell(
+                    content,
+                    edges[index],
+                    y,
+                    width,
+                    subtitles_height,
+                    subtitle,
+                    REGULAR,
+                );
+            }
+        }
+        start = end;
+    }
+}
+
+/// A cell of the header, its text centered and wrapped on several lines
+fn header_cell(
+    content: &mut Content,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    text: &str,
+    font: Name,
+) {
+    content.rect(x, y, width, height).stroke();
+    let lines = wrap(text, width - 2.0 * CELL_PADDING, HEADER_FONT_SIZE);
+    let line_height = HEADER_FONT_SIZE * 1.15;
+    let text_height = line_height * lines.len() as f32;
+    let mut line_y = y + (height + text_height) / 2.0 - HEADER_FONT_SIZE * 0.85;
+    for line in lines {
+        let line_x = x + (width - text_width(&line, HEADER_FONT_SIZE)) / 2.0;
+        show(content, font, HEADER_FONT_SIZE, line_x, line_y, &line);
+        line_y -= line_height;
+    }
+}
+
+/// Write a line of text, its baseline at y
+fn show(content: &mut Content, font: Name, size: f32, x: f32, y: f32, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(text)))
+        .end_text();
+}
+
+/// Split a text in lines of at most width, between the words
+fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if text_width(&format!("{line} {word}"), size) <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// The text cut to fit in width
+fn fit(text: &str, width: f32, size: f32) -> String {
+    let mut fitted = text.to_string();
+    while text_width(&fitted, size) > width && fitted.pop().is_some() {}
+    fitted
+}
+
+/// Widths of the printable ascii characters in Helvetica,
+/// in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+    278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0 to 9
+    278, 278, 584, 584, 584, 556, 1015, // : to @
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // A to M
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // N to Z
+    278, 278, 278, 469, 556, 333, // [ to `
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // a to m
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // n to z
+    334, 260, 334, 584, // { to ~
+];
+
+/// Width of a text in points. Bold is slightly wider, the cells keep
+/// a padding for it.
+fn text_width(text: &str, size: f32) -> f32 {
+    let thousandths: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - ' ' as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    thousandths as f32 * size / 1000.0
+}
+
+/// Encode a text for the WinAnsiEncoding of the standard fonts,
+/// the characters it has not are replaced by ?
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[test]
+fn pdf_sheets() {
+    use crate::flightlistgenerator::flighttime::TimeBase;
+    use crate::flightlistgenerator::FlightList;
+    use crate::renderable::logbook::Pagination;
+
+    let flight_list = FlightList::load_from_yaml(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/logbook.yaml"
+    ))
+    .unwrap();
+    let contains =
+        |pdf: &[u8], pattern: &[u8]| pdf.windows(pattern.len()).any(|window| window == pattern);
+    let shelf =
+        JeppesenBookShelfHtml::build_from(&flight_list, TimeBase::Utc, Pagination::default());
+    let pdf = jeppesen_pdf(&shelf, &PilotIdentity::default());
+    assert!(pdf.starts_with(b"%PDF"));
+    // two pages of the logbook, a left and a right sheet each
+    assert!(contains(&pdf, b"/Count 4"));
+    assert!(contains(&pdf, b"(TOTAL FROM PREVIOUS PAGES)"));
+    assert!(contains(&pdf, b"dd/mm/yyyy"));
+
+    // a page of 50 lines, 39 on the first sheets, 11 and the totals on the next ones
+    assert_eq!(rows_per_sheet(), 39);
+    let mut shelf = shelf;
+    let page = &mut shelf.bookshelf[0].pages[0];
+    page.flightlinelist = flight_list
+        .flights
+        .iter()
+        .cycle()
+        .take(50)
+        .cloned()
+        .collect();
+    let pdf = jeppesen_pdf(&shelf, &PilotIdentity::default());
+    assert!(contains(&pdf, b"/Count 6"));
+    assert!(contains(&pdf, b"(page 1, sheet 2/2)"));
+    assert_eq!(win_ansi("Genève’s"), b"Gen\xe8ve\x92s");
+}
@@ -1,18 +1,81 @@
 use anyhow::{bail, Context, Result};
 use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
 use strict_yaml_rust::StrictYaml;
 
-#[derive(Clone)]
+use super::diagnostic::FieldError;
+use super::utils::{format_unknown_keys, mandatory_string, unknown_keys};
+
+/// Keys read by AircraftDataBase::add_flight
+pub const AIRCRAFT_KEYS: &[&str] = &[
+    "immatriculation",
+    "acmodel",
+    "actype",
+    "nb_engines",
+    "mtow",
+    "multipilot",
+    "cat1",
+    "cat2",
+    "cat3",
+];
+
+/// Keys of an element of the aircraft catalog
+const AIRCRAFT_CATALOG_KEYS: &[&str] = &[
+    "acmodel",
+    "actype",
+    "nb_engines",
+    "mtow",
+    "multipilot",
+    "cat1",
+    "cat2",
+    "cat3",
+    "immatriculations",
+];
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct AircraftModel {
     pub nb_engines: u8,
     pub mtow: u32, // MTOW in kg
     pub multipilot: bool,
+    #[serde(rename = "cat1")]
     pub category_1: String,
+    #[serde(rename = "cat2")]
     pub category_2: String,
+    #[serde(rename = "cat3")]
     pub category_3: String,
 }
 
+impl AircraftModel {
+    fn from_strings(
+        nb_engines: &str,
+        mtow: &str,
+        multipilot: &str,
+        category_1: &str,
+        category_2: &str,
+        category_3: &str,
+    ) -> Result<Self> {
+        let nb_engines = nb_engines
+            .parse::<u8>()
+            .with_context(|| format!("nb engines: {nb_engines}"))?;
+        let mtow = mtow
+            .parse::<u32>()
+            .with_context(|| format!("mtow: {mtow}"))?;
+        let multipilot = multipilot
+            .parse::<bool>()
+            .with_context(|| format!("multipilot: {multipilot:?}"))?;
+        Ok(AircraftModel {
+            nb_engines,
+            mtow,
+            multipilot,
+            category_1: category_1.to_string(),
+            category_2: category_2.to_string(),
+            category_3: category_3.to_string(),
+        })
+    }
+}
+
 /// Relation between immatriculation, aircraft model and family
+#[derive(Clone)]
 pub struct AircraftDataBase {
     // immatriculation -> aircraftmodel name
     immatriculations: IndexMap<String, String>,
@@ -22,6 +85,35 @@ pub struct AircraftDataBase {
     families: IndexMap<String, String>,
 }
 
+/// A model of the database with its family and immatriculations, as serialized
+#[derive(Serialize)]
+struct AircraftModelEntry<'a> {
+    name: &'a str,
+    family: &'a str,
+    #[serde(flatten)]
+    model: &'a AircraftModel,
+    immatriculations: Vec<&'a str>,
+}
+
+/// Serialized as the list of its models
+impl Serialize for AircraftDataBase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.models.iter().map(|(name, model)| {
+            AircraftModelEntry {
+                name,
+                family: &self.families[name],
+                model,
+                immatriculations: self
+                    .immatriculations
+                    .iter()
+                    .filter(|(_, model_name)| *model_name == name)
+                    .map(|(immatriculation, _)| immatriculation.as_str())
+                    .collect(),
+            }
+        }))
+    }
+}
+
 impl AircraftDataBase {
     pub fn empty() -> Self {
         AircraftDataBase {
@@ -31,6 +123,97 @@ impl AircraftDataBase {
         }
     }
 
+    /// parse an element of the aircraft catalog :
+    /// a model definition with its immatriculations
+    pub fn add_catalog_entry(&mut self, yaml: &StrictYaml) -> Result<()> {
+        let unknown = unknown_keys(yaml, &[AIRCRAFT_CATALOG_KEYS]);
+        if !unknown.is_empty() {
+            bail!("Unknown keys : {}", format_unknown_keys(&unknown));
+        }
+        let aircraft_model_name = mandatory_string(yaml, "acmodel")?;
+        let aircraft_model = AircraftModel::from_strings(
+            mandatory_string(yaml, "nb_engines")?,
+            mandatory_string(yaml, "mtow")?,
+            mandatory_string(yaml, "multipilot")?,
+            mandatory_string(yaml, "cat1")?,
+            mandatory_string(yaml, "cat2")?,
+            mandatory_string(yaml, "cat3")?,
+        )
+        .with_context(|| FieldError::new("acmodel", format!("model {aircraft_model_name}")))?;
+        self.add_model(
+            aircraft_model_name,
+            mandatory_string(yaml, "actype")?,
+            aircraft_model,
+        )
+        .with_context(|| FieldError::new("acmodel", format!("model {aircraft_model_name}")))?;
+        match &yaml["immatriculations"] {
+            StrictYaml::Array(immatriculations) => {
+                for immatriculation in immatriculations {
+                    let immatriculation = immatriculation.as_str().with_context(|| {
+                        FieldError::new("immatriculations", "immatriculations must be strings")
+                    })?;
+                    self.add_immatriculation(immatriculation, aircraft_model_name)
+                        .with_context(|| FieldError::new("immatriculations", "immatriculations"))?;
+                }
+            }
+            StrictYaml::BadValue => {}
+            _ => bail!(FieldError::new(
+                "immatriculations",
+                "[immatriculations] must be a list"
+            )),
+        }
+        Ok(())
+    }
+
+    /// Add a model and its family.
+    /// A model already in the database is accepted if the definition is the same,
+    /// so that a flight can repeat a definition of the aircraft catalog.
+    fn add_model(
+        &mut self,
+        aircraft_model_name: &str,
+        aircraft_family_name: &str,
+        aircraft_model: AircraftModel,
+    ) -> Result<()> {
+        if let Some(existing_model) = self.models.get(aircraft_model_name) {
+            let existing_family = self.families.get(aircraft_model_name).unwrap();
+            if *existing_model != aircraft_model || existing_family != aircraft_family_name {
+                bail!(
+                    "Error : model {aircraft_model_name} already in \
+                     the dictionary AircraftDataBase.models with another definition"
+                );
+            }
+            return Ok(());
+        }
+        self.models
+            .insert(aircraft_model_name.to_string(), aircraft_model);
+        self.families.insert(
+            aircraft_model_name.to_string(),
+            aircraft_family_name.to_string(),
+        );
+        Ok(())
+    }
+
+    /// Associate an immatriculation to a model.
+    /// An immatriculation can only have one model.
+    fn add_immatriculation(
+        &mut self,
+        immatriculation: &str,
+        aircraft_model_name: &str,
+    ) -> Result<()> {
+        match self.immatriculations.get(immatriculation) {
+            Some(existing_model_name) if existing_model_name == aircraft_model_name => Ok(()),
+            Some(existing_model_name) => bail!(
+                "Error : immatriculation {immatriculation} has already \
+                 the model {existing_model_name} associated"
+            ),
+            None => {
+                self.immatriculations
+                    .insert(immatriculation.to_string(), aircraft_model_name.to_string());
+                Ok(())
+            }
+        }
+    }
+
     /// parse a yaml flight and adds all elements in the database
     pub fn add_flight(&mut self, yaml: &StrictYaml) -> Result<(&str, &str)> {
         let immatriculation = yaml["immatriculation"]
@@ -58,45 +241,12 @@ impl AircraftDataBase {
                 Some(category_2),
                 Some(category_3),
             ) => {
-                if self.immatriculations.contains_key(immatriculation) {
-                    bail!(
-                        "Error : immatriculation {immatriculation} already in \
-			 the dictionary AircraftDataBase.immatriculations "
-                    );
-                }
-                if self.models.contains_key(aircraft_model_name) {
-                    bail!(
-                        "Error : model {aircraft_model_name} already in \
-			 the dictionary AircraftDataBase.models "
-                    );
-                }
-                let aircraft_family_name = aircraft_family_name.to_string();
-                let nb_engines = nb_engines
-                    .parse::<u8>()
-                    .with_context(|| format!("nb engines: {nb_engines}"))?;
-                let mtow = mtow
-                    .parse::<u32>()
-                    .with_context(|| format!("mtow: {mtow}"))?;
-                let multipilot = multipilot
-                    .parse::<bool>()
-                    .with_context(|| format!("multipilot: {multipilot:?}"))?;
-
-                //Create new AircrafModel :
-                let aircraft_model = AircraftModel {
-                    nb_engines,
-                    mtow,
-                    multipilot,
-                    category_1: category_1.to_string(),
-                    category_2: category_2.to_string(),
-                    category_3: category_3.to_string(),
-                };
+                let aircraft_model = AircraftModel::from_strings(
+                    nb_engines, mtow, multipilot, category_1, category_2, category_3,
+                )?;
                 // add the aircraft model,family and immatriculation :
-                self.models
-                    .insert(aircraft_model_name.to_string(), aircraft_model);
-                self.immatriculations
-                    .insert(immatriculation.to_string(), aircraft_model_name.to_string());
-                self.families
-                    .insert(aircraft_model_name.to_string(), aircraft_family_name);
+                self.add_model(aircraft_model_name, aircraft_family_name, aircraft_model)?;
+                self.add_immatriculation(immatriculation, aircraft_model_name)?;
             } // case 1
             // case 2) : no information provided but the immatriculation
             (None, None, None, None, None, None, None, None) => {
@@ -106,16 +256,10 @@ impl AircraftDataBase {
             } // case 2
             // Case 3) Immatriculation and model
             (Some(aircraft_model_name), None, None, None, None, None, None, None) => {
-                if self.immatriculations.contains_key(immatriculation) {
-                    bail!(
-                        "Error : immatriculation {immatriculation} has already a model associated"
-                    );
-                }
                 if !self.models.contains_key(aircraft_model_name) {
                     bail!("Error : model {aircraft_model_name} has not been previously defined");
                 }
-                self.immatriculations
-                    .insert(immatriculation.to_string(), aircraft_model_name.to_string());
+                self.add_immatriculation(immatriculation, aircraft_model_name)?;
             } // case 3
             _ => {
                 bail!(
@@ -151,6 +295,12 @@ impl AircraftDataBase {
             .unwrap();
         Ok((immatriculation_ref, model_ref))
     }
+    pub fn has_immatriculation(&self, immatriculation: &str) -> bool {
+        self.immatriculations.contains_key(immatriculation)
+    }
+    pub fn has_model(&self, aircraft_model_name: &str) -> bool {
+        self.models.contains_key(aircraft_model_name)
+    }
     /// Input: immatriculation
     /// Outptut: immatriculation,aircraft model
     pub fn get_model_name(&self, immatriculation: &str) -> Result<(&str, &str)> {
@@ -160,6 +310,12 @@ impl AircraftDataBase {
             .with_context(|| format!("immatriculation {immatriculation} not found"))?;
         Ok((immatriculation_ref, model_ref))
     }
+    /// Input: immatriculation
+    /// Output: the aircraft model
+    pub fn get_model(&self, immatriculation: &str) -> Result<&AircraftModel> {
+        let (_, model_name) = self.get_model_name(immatriculation)?;
+        Ok(self.models.get(model_name).unwrap())
+    }
     pub fn get_family_name(&self, immatriculation: &str) -> Result<&str> {
         let (_, model_name) = self.get_model_name(immatriculation)?;
         Ok(self.families.get(model_name).unwrap())
@@ -192,11 +348,39 @@ impl AircraftDataBase {
         }
     }
     // todo try to name lifetime 'a
-    pub fn iter_models_families(&self) -> indexmap::map::Iter<String, String> {
+    pub fn iter_models_families(&self) -> indexmap::map::Iter<'_, String, String> {
         self.families.iter()
     }
 
-    pub fn iter_models(&self) -> indexmap::map::Iter<String, AircraftModel> {
+    pub fn iter_models(&self) -> indexmap::map::Iter<'_, String, AircraftModel> {
         self.models.iter()
     }
 }
+
+#[test]
+fn catalog_entries() {
+    let entry = |mtow: &str, immatriculation: &str| {
+        let yaml = format!(
+            "acmodel: C172\nactype: SEP\nnb_engines: 1\nmtow: {mtow}\nmultipilot: false\n\
+             cat1: light\ncat2: none\ncat3: none\nimmatriculations:\n  - {immatriculation}"
+        );
+        strict_yaml_rust::StrictYamlLoader::load_from_str(&yaml)
+            .unwrap()
+            .remove(0)
+    };
+    let mut aircrafts = AircraftDataBase::empty();
+    aircrafts
+        .add_catalog_entry(&entry("1200", "ABCDEF"))
+        .unwrap();
+    // the same model with another immatriculation
+    aircrafts
+        .add_catalog_entry(&entry("1200", "BCDEFG"))
+        .unwrap();
+    assert_eq!(aircrafts.get_model("BCDEFG").unwrap().mtow, 1200);
+    assert_eq!(aircrafts.get_family_name("ABCDEF").unwrap(), "SEP");
+    // another definition of the model
+    assert!(aircrafts
+        .add_catalog_entry(&entry("1100", "CDEFGH"))
+        .is_err());
+    assert!(aircrafts.get_model("CDEFGH").is_err());
+}
@@ -1,18 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
+use serde::{Serialize, Serializer};
 use strict_yaml_rust::StrictYaml;
+use time_tz::{timezones, TimeZone, Tz};
+
+use super::airportreference::{AirportReference, ReferenceAirport};
+use super::diagnostic::FieldError;
+use super::geo::Coordinates;
+use super::utils::{format_unknown_keys, mandatory_string, optional_string, unknown_keys};
+
+/// Keys of an element of the airport catalog
+const AIRPORT_CATALOG_KEYS: &[&str] = &[
+    "iata",
+    "icao",
+    "name",
+    "tz",
+    "lat",
+    "lon",
+    "elevation",
+    "country",
+];
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AirportList {
-    //content: HashSet<Airport>,
     content: Vec<Airport>,
+    // index in content of each code and name, without the ??? and ???? placeholders
+    by_iata: HashMap<String, usize>,
+    by_icao: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+    // offline reference data, to resolve and check the codes
+    // shared by the copies of the list
+    reference: Option<Arc<AirportReference>>,
 }
 
-#[derive(Debug)]
+/// Serialized as the list of its airports
+impl Serialize for AirportList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.content.iter())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct Airport {
     iata: String,
     icao: String,
     name: String,
+    #[serde(flatten)]
+    details: AirportDetails,
+}
+
+/// Optional data of an airport, from the catalog or from the flights
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct AirportDetails {
+    /// IANA time zone, like Europe/Paris
+    #[serde(serialize_with = "serialize_time_zone")]
+    pub tz: Option<&'static Tz>,
+    /// latitude and longitude in decimal degrees
+    pub coordinates: Option<Coordinates>,
+    /// elevation in feet
+    pub elevation: Option<i32>,
+    /// ISO 3166 country code, like FR
+    pub country: Option<String>,
+}
+
+/// Serialize a time zone as its IANA name
+fn serialize_time_zone<S: Serializer>(
+    tz: &Option<&'static Tz>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match tz {
+        Some(tz) => serializer.serialize_str(tz.name()),
+        None => serializer.serialize_none(),
+    }
 }
 
 impl Airport {
@@ -21,18 +84,137 @@ impl Airport {
             iata: iata.to_string(),
             icao: icao.to_string(),
             name: name.to_string(),
+            details: AirportDetails::default(),
+        }
+    }
+
+    fn from_reference(airport: &ReferenceAirport) -> Self {
+        Airport {
+            iata: airport.iata.clone().unwrap_or("???".to_string()),
+            icao: airport.icao.clone().unwrap_or("????".to_string()),
+            name: airport.name.clone(),
+            details: airport.details.clone(),
         }
     }
 }
 
+impl AirportDetails {
+    /// read the details keys of a yaml element,
+    /// prefix : "" in the catalog, apt_departure_ or apt_arrival_ in a flight
+    pub fn from_yaml(yaml: &StrictYaml, prefix: &str) -> Result<Self> {
+        let key = |name: &str| format!("{prefix}{name}");
+        let tz = match optional_string(yaml, &key("tz")) {
+            Some(tz_name) => Some(timezones::get_by_name(tz_name).with_context(|| {
+                FieldError::new(&key("tz"), format!("unknown time zone {tz_name}"))
+            })?),
+            None => None,
+        };
+        let degrees = |name: &str| -> Result<Option<f64>> {
+            let key = key(name);
+            match optional_string(yaml, &key) {
+                Some(value) => value.trim().parse::<f64>().map(Some).with_context(|| {
+                    FieldError::new(&key, format!("{value} is not a number of degrees"))
+                }),
+                None => Ok(None),
+            }
+        };
+        let coordinates = match (degrees("lat")?, degrees("lon")?) {
+            (Some(latitude), Some(longitude)) => Some(
+                Coordinates::new(latitude, longitude)
+                    .with_context(|| FieldError::new(&key("lat"), "airport position"))?,
+            ),
+            (None, None) => None,
+            (Some(_), None) => bail!(FieldError::new(&key("lat"), "[lon] missing with [lat]")),
+            (None, Some(_)) => bail!(FieldError::new(&key("lon"), "[lat] missing with [lon]")),
+        };
+        let elevation = match optional_string(yaml, &key("elevation")) {
+            Some(value) => Some(value.trim().parse::<i32>().with_context(|| {
+                FieldError::new(
+                    &key("elevation"),
+                    format!("{value} is not an elevation in feet"),
+                )
+            })?),
+            None => None,
+        };
+        let country = match optional_string(yaml, &key("country")) {
+            Some(code) if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) => {
+                Some(code.to_ascii_uppercase())
+            }
+            Some(code) => bail!(FieldError::new(
+                &key("country"),
+                format!("{code} is not a two letters country code")
+            )),
+            None => None,
+        };
+        Ok(AirportDetails {
+            tz,
+            coordinates,
+            elevation,
+            country,
+        })
+    }
+
+    /// Take the details of other which are missing,
+    /// fails with the key of the first detail different in both
+    fn merge(&mut self, other: AirportDetails) -> std::result::Result<(), (&'static str, String)> {
+        fn merge_field<T: PartialEq>(
+            current: &mut Option<T>,
+            other: Option<T>,
+            key: &'static str,
+            describe: impl Fn(&T) -> String,
+        ) -> std::result::Result<(), (&'static str, String)> {
+            match (current.as_ref(), other) {
+                (Some(existing), Some(other)) if *existing != other => {
+                    Err((key, describe(existing)))
+                }
+                (None, Some(other)) => {
+                    *current = Some(other);
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        // time zones are compared by name
+        match (self.tz, other.tz) {
+            (Some(existing), Some(other)) if existing.name() != other.name() => {
+                return Err(("tz", format!("the time zone {}", existing.name())));
+            }
+            (None, Some(other)) => self.tz = Some(other),
+            _ => {}
+        }
+        merge_field(
+            &mut self.coordinates,
+            other.coordinates,
+            "lat",
+            |position| format!("the position {} {}", position.latitude, position.longitude),
+        )?;
+        merge_field(&mut self.elevation, other.elevation, "elevation", |feet| {
+            format!("the elevation {feet} ft")
+        })?;
+        merge_field(&mut self.country, other.country, "country", |country| {
+            format!("the country {country}")
+        })
+    }
+}
+
 /// Todo
 /// error if same icao or same name
 impl AirportList {
     pub fn new() -> Self {
         AirportList {
             content: Vec::new(),
+            by_iata: HashMap::new(),
+            by_icao: HashMap::new(),
+            by_name: HashMap::new(),
+            reference: None,
         }
     }
+
+    /// Use reference data : the bare codes which are not in the list
+    /// are taken from it, and the codes which are not in it are rejected
+    pub fn set_reference(&mut self, reference: AirportReference) {
+        self.reference = Some(Arc::new(reference));
+    }
     /// Add an airport performs checks
     /// and returns best name
     pub fn add(
@@ -41,36 +223,80 @@ impl AirportList {
         icao: &StrictYaml,
         name: &StrictYaml,
     ) -> Result<String> {
-        let iata = iata.as_str();
-        let icao = icao.as_str();
         let name = name.as_str();
+        // with a name, a missing code stands for its placeholder
+        let (iata, icao) = match (iata.as_str(), icao.as_str(), name) {
+            (Some(iata), None, Some(_)) => (Some(iata), Some("????")),
+            (None, Some(icao), Some(_)) => (Some("???"), Some(icao)),
+            (iata, icao, _) => (iata, icao),
+        };
         //
         match (iata, icao, name) {
             // Case 1 iata and icao and name are defined
             (Some(iata), Some(icao), Some(name)) => {
-                if self.has_iata(iata) && iata != "???" {
+                // same definition as in the airport catalog
+                if self.by_name.get(name).is_some_and(|index| {
+                    let airport = &self.content[*index];
+                    airport.iata == iata && airport.icao == icao
+                }) {
+                    return Ok(best_name(iata, icao, name));
+                }
+                if let Some(reference) = &self.reference {
+                    if iata != "???" && reference.find_iata(iata).is_none() {
+                        bail!(
+                            "iata:{} does not exist in the airport reference data.",
+                            iata
+                        );
+                    }
+                    if icao != "????" && reference.find_icao(icao).is_none() {
+                        bail!(
+                            "icao:{} does not exist in the airport reference data.",
+                            icao
+                        );
+                    }
+                    if let (Some(by_iata), Some(by_icao)) =
+                        (reference.find_iata(iata), reference.find_icao(icao))
+                    {
+                        if !std::ptr::eq(by_iata, by_icao) {
+                            bail!(
+                                "iata:{} and icao:{} are two airports in the airport reference data.",
+                                iata,
+                                icao
+                            );
+                        }
+                    }
+                }
+                if self.has_iata(iata) {
                     bail!("iata:{} already in Airport List.", iata);
-                } else if self.has_icao(icao) && icao != "????" {
+                } else if self.has_icao(icao) {
                     bail!("icao:{} already in Airport List.", icao);
                 } else if self.has_name(name) {
                     bail!("name:{} already in Airport List.", name);
                 } else {
-                    self.content.push(Airport::new(iata, icao, name));
+                    self.push(Airport::new(iata, icao, name));
                     Ok(best_name(iata, icao, name))
                 }
             }
             // Case 2
             (Some(iata), None, None) => {
+                let reference = self.reference.clone();
                 if self.has_iata(iata) {
                     Ok(iata.to_string())
+                } else if let Some(airport) = reference.as_ref().and_then(|r| r.find_iata(iata)) {
+                    self.push_reference(airport);
+                    Ok(iata.to_string())
                 } else {
                     bail!("iata {} not in database", iata);
                 }
             }
             // Case 3
             (None, Some(icao), None) => {
+                let reference = self.reference.clone();
                 if self.has_icao(icao) {
                     Ok(icao.to_string())
+                } else if let Some(airport) = reference.as_ref().and_then(|r| r.find_icao(icao)) {
+                    self.push_reference(airport);
+                    Ok(icao.to_string())
                 } else {
                     bail!("icao {} not in database", icao);
                 }
@@ -99,31 +325,152 @@ impl AirportList {
         } // match
     }
 
-    fn has_iata(&self, iata: &str) -> bool {
-        for airport in self.content.iter() {
-            if iata == airport.iata {
-                return true;
-            }
+    /// Add an airport to the list, its codes and name
+    /// already used by another airport keep designating it
+    fn push(&mut self, airport: Airport) {
+        let index = self.content.len();
+        if airport.iata != "???" {
+            self.by_iata.entry(airport.iata.clone()).or_insert(index);
+        }
+        if airport.icao != "????" {
+            self.by_icao.entry(airport.icao.clone()).or_insert(index);
         }
-        false
+        self.by_name.entry(airport.name.clone()).or_insert(index);
+        self.content.push(airport);
     }
 
-    fn has_icao(&self, icao: &str) -> bool {
-        for airport in self.content.iter() {
-            if icao == airport.icao {
-                return true;
+    /// Add an airport of the reference data.
+    /// If its other code already designates an airport of the list,
+    /// defined in a flight or the catalog, its codes designate that one.
+    fn push_reference(&mut self, airport: &ReferenceAirport) {
+        let existing = airport
+            .iata
+            .as_ref()
+            .and_then(|iata| self.by_iata.get(iata))
+            .or_else(|| {
+                airport
+                    .icao
+                    .as_ref()
+                    .and_then(|icao| self.by_icao.get(icao))
+            })
+            .copied();
+        match existing {
+            Some(index) => {
+                if let Some(iata) = &airport.iata {
+                    self.by_iata.entry(iata.clone()).or_insert(index);
+                }
+                if let Some(icao) = &airport.icao {
+                    self.by_icao.entry(icao.clone()).or_insert(index);
+                }
             }
+            None => self.push(Airport::from_reference(airport)),
         }
-        false
     }
 
-    fn has_name(&self, name: &str) -> bool {
-        for airport in self.content.iter() {
-            if name == airport.name {
-                return true;
-            }
+    /// Add the details of an airport already in the list,
+    /// prefix : the prefix of the detail keys, to locate a conflict
+    pub fn add_details(
+        &mut self,
+        iata_icao_or_name: &str,
+        details: AirportDetails,
+        prefix: &str,
+    ) -> Result<()> {
+        let index = self
+            .find_index(iata_icao_or_name)
+            .with_context(|| format!("airport {iata_icao_or_name} not in database"))?;
+        if let Err((key, existing)) = self.content[index].details.merge(details) {
+            bail!(FieldError::new(
+                &format!("{prefix}{key}"),
+                format!("airport {iata_icao_or_name} has already {existing}")
+            ));
+        }
+        Ok(())
+    }
+
+    /// true if both designate the same airport,
+    /// each one can be an iata, an icao or a name
+    pub fn same_airport(&self, airport_a: &str, airport_b: &str) -> bool {
+        if airport_a == airport_b {
+            return true;
+        }
+        match (self.find_index(airport_a), self.find_index(airport_b)) {
+            (Some(airport_a), Some(airport_b)) => airport_a == airport_b,
+            _ => false,
+        }
+    }
+
+    fn find_index(&self, iata_icao_or_name: &str) -> Option<usize> {
+        self.by_iata
+            .get(iata_icao_or_name)
+            .or_else(|| self.by_icao.get(iata_icao_or_name))
+            .or_else(|| self.by_name.get(iata_icao_or_name))
+            .copied()
+    }
+
+    /// Details of an airport, given by its iata, icao or name
+    pub fn details(&self, iata_icao_or_name: &str) -> Option<&AirportDetails> {
+        self.find_index(iata_icao_or_name)
+            .map(|index| &self.content[index].details)
+    }
+
+    /// Time zone of an airport, if known
+    pub fn time_zone(&self, iata_icao_or_name: &str) -> Option<&'static Tz> {
+        self.details(iata_icao_or_name)
+            .and_then(|details| details.tz)
+    }
+
+    /// Position of an airport, if known
+    pub fn coordinates(&self, iata_icao_or_name: &str) -> Option<Coordinates> {
+        self.details(iata_icao_or_name)
+            .and_then(|details| details.coordinates)
+    }
+
+    /// parse an element of the airport catalog,
+    /// with the keys iata, icao and name,
+    /// and optionally tz, lat, lon, elevation and country
+    pub fn add_catalog_entry(&mut self, yaml: &StrictYaml) -> Result<()> {
+        let unknown = unknown_keys(yaml, &[AIRPORT_CATALOG_KEYS]);
+        if !unknown.is_empty() {
+            bail!("Unknown keys : {}", format_unknown_keys(&unknown));
         }
-        false
+        let iata = mandatory_string(yaml, "iata")?;
+        let icao = mandatory_string(yaml, "icao")?;
+        let name = mandatory_string(yaml, "name")?;
+        let details = AirportDetails::from_yaml(yaml, "")?;
+        let best_name = self
+            .add(&yaml["iata"], &yaml["icao"], &yaml["name"])
+            .with_context(|| FieldError::new("iata", format!("airport {iata} {icao} {name}")))?;
+        self.add_details(&best_name, details, "")
+    }
+
+    /// true if the iata code is in the list or in the reference data
+    pub fn knows_iata(&self, iata: &str) -> bool {
+        self.has_iata(iata)
+            || self
+                .reference
+                .as_ref()
+                .is_some_and(|reference| reference.find_iata(iata).is_some())
+    }
+
+    /// true if the icao code is in the list or in the reference data
+    pub fn knows_icao(&self, icao: &str) -> bool {
+        self.has_icao(icao)
+            || self
+                .reference
+                .as_ref()
+                .is_some_and(|reference| reference.find_icao(icao).is_some())
+    }
+
+    pub fn has_iata(&self, iata: &str) -> bool {
+        self.by_iata.contains_key(iata)
+    }
+
+    pub fn has_icao(&self, icao: &str) -> bool {
+        self.by_icao.contains_key(icao)
+    }
+
+    pub fn has_name(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
     }
 }
 
@@ -136,3 +483,49 @@ fn best_name(iata: &str, icao: &str, name: &str) -> String {
         name.to_string()
     }
 }
+
+#[test]
+fn airport_details_merge() {
+    let yaml = |input: &str| {
+        strict_yaml_rust::StrictYamlLoader::load_from_str(input)
+            .unwrap()
+            .remove(0)
+    };
+    let mut airports = AirportList::new();
+    airports
+        .add_catalog_entry(&yaml(
+            "iata: GNB\nicao: LFLS\nname: Grenoble\ntz: Europe/Paris\nelevation: \"1302\"",
+        ))
+        .unwrap();
+    // details given later in a flight complete the catalog
+    let flight = yaml("apt_departure_icao: LFLS\napt_departure_country: fr");
+    let details = AirportDetails::from_yaml(&flight, "apt_departure_").unwrap();
+    airports
+        .add_details("LFLS", details, "apt_departure_")
+        .unwrap();
+    let details = airports.details("Grenoble").unwrap();
+    assert_eq!(details.elevation, Some(1302));
+    assert_eq!(details.country.as_deref(), Some("FR"));
+    assert!(airports.same_airport("GNB", "LFLS"));
+    // a different value is an error located on its key
+    let flight = yaml("apt_arrival_iata: GNB\napt_arrival_elevation: \"1000\"");
+    let details = AirportDetails::from_yaml(&flight, "apt_arrival_").unwrap();
+    let error = airports
+        .add_details("GNB", details, "apt_arrival_")
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<FieldError>().unwrap().key,
+        "apt_arrival_elevation"
+    );
+    // lat without lon
+    let flight = yaml("apt_arrival_lat: \"45.3\"");
+    assert!(AirportDetails::from_yaml(&flight, "apt_arrival_").is_err());
+
+    // the same catalog entry again, then another name for the codes
+    airports
+        .add_catalog_entry(&yaml("iata: GNB\nicao: LFLS\nname: Grenoble"))
+        .unwrap();
+    assert!(airports
+        .add_catalog_entry(&yaml("iata: GNB\nicao: LFLS\nname: Grenoble Isere"))
+        .is_err());
+}
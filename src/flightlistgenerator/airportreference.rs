@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::airport::AirportDetails;
+use super::diagnostic::{Diagnostic, Diagnostics, Severity};
+use super::geo::Coordinates;
+use super::sourcemap::Position;
+
+/// Offline airport reference data, read from an OurAirports csv file
+/// (https://ourairports.com/data/airports.csv).
+/// A bare iata or icao code of a flight is resolved with it,
+/// and the codes which are not in it are rejected.
+#[derive(Debug, Default)]
+pub struct AirportReference {
+    airports: Vec<ReferenceAirport>,
+    by_iata: HashMap<String, usize>,
+    by_icao: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+pub struct ReferenceAirport {
+    pub iata: Option<String>,
+    pub icao: Option<String>,
+    pub name: String,
+    pub details: AirportDetails,
+}
+
+/// The columns used in the OurAirports csv
+#[derive(Debug, Deserialize)]
+struct OurAirportsRow {
+    ident: String,
+    name: String,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    #[serde(default)]
+    elevation_ft: Option<f64>,
+    #[serde(default)]
+    iso_country: Option<String>,
+    #[serde(default)]
+    icao_code: Option<String>,
+    #[serde(default)]
+    gps_code: Option<String>,
+    #[serde(default)]
+    iata_code: Option<String>,
+}
+
+impl AirportReference {
+    /// Read the csv file, a row which cannot be read goes to the diagnostics
+    pub fn from_csv_file(file: &Path, diagnostics: &mut Diagnostics) -> Result<Self> {
+        let file_name = file.to_string_lossy();
+        let mut reader = csv::Reader::from_path(file)
+            .with_context(|| format!("Cannot open airport data : {file_name}"))?;
+        let mut reference = AirportReference::default();
+        for row in reader.deserialize::<OurAirportsRow>() {
+            match row {
+                Ok(row) => reference.push(row),
+                Err(e) => diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message: e.to_string(),
+                    file: file_name.to_string(),
+                    position: e.position().map(|position| Position {
+                        line: position.line() as usize,
+                        column: 1,
+                    }),
+                    flight_index: None,
+                    date_start: None,
+                    snippet: None,
+                }),
+            }
+        }
+        log::info!("{} airports in {file_name}", reference.airports.len());
+        Ok(reference)
+    }
+
+    /// Add a row, the first airport of a code is kept.
+    /// The icao code is icao_code, or gps_code, or ident
+    /// when it looks like an icao code.
+    fn push(&mut self, row: OurAirportsRow) {
+        let is_icao = |code: &String| {
+            code.len() == 4
+                && code
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        };
+        let icao = [row.icao_code, row.gps_code, Some(row.ident)]
+            .into_iter()
+            .flatten()
+            .find(is_icao);
+        let iata = row
+            .iata_code
+            .filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_alphanumeric()));
+        let index = self.airports.len();
+        if let Some(icao) = &icao {
+            self.by_icao.entry(icao.clone()).or_insert(index);
+        }
+        if let Some(iata) = &iata {
+            self.by_iata.entry(iata.clone()).or_insert(index);
+        }
+        self.airports.push(ReferenceAirport {
+            iata,
+            icao,
+            name: row.name,
+            details: AirportDetails {
+                tz: None,
+                coordinates: Coordinates::new(row.latitude_deg, row.longitude_deg).ok(),
+                elevation: row.elevation_ft.map(|feet| feet.round() as i32),
+                country: row.iso_country,
+            },
+        });
+    }
+
+    pub fn find_iata(&self, iata: &str) -> Option<&ReferenceAirport> {
+        self.by_iata.get(iata).map(|index| &self.airports[*index])
+    }
+
+    pub fn find_icao(&self, icao: &str) -> Option<&ReferenceAirport> {
+        self.by_icao.get(icao).map(|index| &self.airports[*index])
+    }
+}
+
+#[test]
+fn reference_resolves_codes() {
+    use super::airport::AirportList;
+    use strict_yaml_rust::StrictYaml;
+    let code = |code: &str| StrictYaml::String(code.to_string());
+    let none = StrictYaml::BadValue;
+    let mut diagnostics = Diagnostics::default();
+    let reference = AirportReference::from_csv_file(
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/samples/reference/ourairports.csv"
+        )),
+        &mut diagnostics,
+    )
+    .unwrap();
+    assert!(diagnostics.is_empty());
+    let mut airports = AirportList::new();
+    airports.set_reference(reference);
+    // a bare code gives the full airport
+    assert_eq!(airports.add(&none, &code("LFLS"), &none).unwrap(), "LFLS");
+    assert!(airports.same_airport("LFLS", "GNB"));
+    let details = airports.details("Grenoble-Isère Airport").unwrap();
+    assert_eq!(details.elevation, Some(1302));
+    assert_eq!(details.country.as_deref(), Some("FR"));
+    assert!(airports.coordinates("GNB").is_some());
+    assert_eq!(airports.add(&code("LHR"), &none, &none).unwrap(), "LHR");
+    // unknown codes are rejected
+    assert!(airports.add(&none, &code("ZZZZ"), &none).is_err());
+    assert!(airports
+        .add(&code("ZZZ"), &code("LFLY"), &code("Lyon Bron"))
+        .is_err());
+    // the iata and icao codes of two different airports
+    assert!(airports
+        .add(&code("GNB"), &code("LFLY"), &code("Lyon Bron"))
+        .is_err());
+    // a bare code of an airport defined with its other code
+    assert_eq!(
+        airports.add(&none, &code("LFGJ"), &code("Dole")).unwrap(),
+        "LFGJ"
+    );
+    assert_eq!(airports.add(&code("DLE"), &none, &none).unwrap(), "DLE");
+    assert!(airports.same_airport("DLE", "Dole"));
+    // no iata placeholder needed
+    assert_eq!(
+        airports
+            .add(&none, &code("LFKX"), &code("Méribel"))
+            .unwrap(),
+        "LFKX"
+    );
+}
@@ -0,0 +1,297 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::diagnostic::{Diagnostics, Severity};
+use super::flightlist::FlightList;
+use super::manifest::Manifest;
+use super::options::LoadOptions;
+
+/// A file written again within this time keeps the same modification
+/// time on some file systems
+const MODIFICATION_TIME_RESOLUTION: Duration = Duration::from_secs(2);
+
+/// The flight list of a logbook, kept between the requests of the web pages.
+///
+/// The logbook is loaded again only when one of its files has changed :
+/// its modification time and size first, then its content if they have changed
+/// or are too recent to be trusted. The flight files of a manifest and the
+/// default catalogs are looked for again, a new file is a change too.
+/// When the new version has errors, the last version without errors is
+/// still served and the failure is kept to be shown with it.
+pub struct FlightListCache {
+    logbook: String,
+    options: LoadOptions,
+    // the files of the logbook, the logbook alone before the first load
+    files: Vec<PathBuf>,
+    // the manifest of the last load, to find its flight files again
+    manifest: Option<Manifest>,
+    fingerprint: Option<Fingerprint>,
+    last_good: Option<FlightList>,
+    failure: Option<LoadFailure>,
+    // number of loads
+    version: u64,
+}
+
+/// Why the logbook could not be loaded
+#[derive(Debug, Clone)]
+pub enum LoadFailure {
+    /// a file cannot be read
+    Unreadable(String),
+    /// the logbook has errors, listed in the diagnostics
+    Errors(Diagnostics),
+}
+
+/// Modification times, sizes and content hash of the files of a logbook
+#[derive(Debug, PartialEq, Eq)]
+struct Fingerprint {
+    modified: Vec<Option<(SystemTime, u64)>>,
+    hash: u64,
+    // when it was taken
+    taken: SystemTime,
+}
+
+impl FlightListCache {
+    /// Nothing is loaded before the first request
+    pub fn new(logbook: &str, options: LoadOptions) -> Self {
+        FlightListCache {
+            logbook: logbook.to_string(),
+            options,
+            files: vec![PathBuf::from(logbook)],
+            manifest: None,
+            fingerprint: None,
+            last_good: None,
+            failure: None,
+            version: 0,
+        }
+    }
+
+    pub fn logbook(&self) -> &str {
+        &self.logbook
+    }
+
+    /// The files of the logbook, as found by the last refresh
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The flight list, loaded again if the logbook has changed.
+    /// It is the last version without errors, or the failure
+    /// if no version could be loaded yet.
+    pub fn flight_list(&mut self) -> Result<&FlightList, &LoadFailure> {
+        self.refresh();
+        match &self.last_good {
+            Some(flight_list) => Ok(flight_list),
+            None => Err(self
+                .failure
+                .as_ref()
+                .expect("a load without flight list is a failure")),
+        }
+    }
+
+    /// Changed by each load of the logbook
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The failure of the last load, when an older version is served instead
+    pub fn stale_failure(&self) -> Option<&LoadFailure> {
+        self.last_good.as_ref().and(self.failure.as_ref())
+    }
+
+    /// Load the logbook again if one of its files has changed
+    pub fn refresh(&mut self) {
+        let files = self.logbook_files();
+        if files == self.files {
+            if let Some(fingerprint) = &mut self.fingerprint {
+                if !fingerprint.changed(&files) {
+                    return;
+                }
+            }
+        }
+        // taken before the load, so that a change during the load is seen next time
+        let fingerprint = Fingerprint::new(&files);
+        self.reload();
+        // the manifest of the new version may list other files
+        let new_files = self.logbook_files();
+        self.fingerprint = Some(if new_files == files {
+            fingerprint
+        } else {
+            Fingerprint::new(&new_files)
+        });
+        self.files = new_files;
+    }
+
+    fn logbook_files(&self) -> Vec<PathBuf> {
+        FlightList::logbook_files(
+            Path::new(&self.logbook),
+            self.manifest.as_ref(),
+            &self.options,
+        )
+    }
+
+    fn reload(&mut self) {
+        log::info!("loading {}", self.logbook);
+        self.version += 1;
+        match FlightList::load_with_diagnostics(&self.logbook, &self.options) {
+            Ok((flight_list, diagnostics)) => {
+                self.manifest = flight_list.manifest.clone();
+                if diagnostics.has_errors() {
+                    log::error!("errors in {}, the last version is kept", self.logbook);
+                    self.failure = Some(LoadFailure::Errors(diagnostics));
+                } else {
+                    self.last_good = Some(flight_list);
+                    self.failure = None;
+                }
+            }
+            Err(e) => {
+                log::error!("{e:#}");
+                self.failure = Some(LoadFailure::Unreadable(format!("{e:#}")));
+            }
+        }
+    }
+}
+
+impl LoadFailure {
+    /// One line per error
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            LoadFailure::Unreadable(message) => vec![message.clone()],
+            LoadFailure::Errors(diagnostics) => diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .map(|diagnostic| diagnostic.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Fingerprint {
+    fn new(files: &[PathBuf]) -> Self {
+        Fingerprint {
+            taken: SystemTime::now(),
+            modified: modification_times(files),
+            hash: content_hash(files),
+        }
+    }
+
+    /// Whether the content of the files has changed.
+    /// The same modification times and sizes are trusted only if they
+    /// are older than the fingerprint by more than their resolution,
+    /// otherwise the content is read again.
+    fn changed(&mut self, files: &[PathBuf]) -> bool {
+        let modified = modification_times(files);
+        let settled = self
+            .modified
+            .iter()
+            .flatten()
+            .all(|(modified, _)| *modified + MODIFICATION_TIME_RESOLUTION < self.taken);
+        if modified == self.modified && settled {
+            return false;
+        }
+        let taken = SystemTime::now();
+        if content_hash(files) != self.hash {
+            return true;
+        }
+        // touched without change
+        self.modified = modified;
+        self.taken = taken;
+        false
+    }
+}
+
+fn modification_times(files: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    files
+        .iter()
+        .map(|file| {
+            let metadata = fs::metadata(file).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+fn content_hash(files: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        fs::read(file).ok().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[test]
+fn reload_on_change() {
+    let directory = std::env::temp_dir().join(format!("zenjep_cache_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let logbook = directory.join("logbook.yaml");
+    let content = fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/logbook.yaml"
+    ))
+    .unwrap();
+    fs::write(&logbook, &content).unwrap();
+    let mut cache = FlightListCache::new(&logbook.to_string_lossy(), LoadOptions::default());
+    assert_eq!(cache.flight_list().unwrap().flights.len(), 4);
+    assert!(cache.stale_failure().is_none());
+    cache.refresh();
+    assert_eq!(cache.version(), 1);
+
+    // the same size, at once : within the resolution of the modification time
+    fs::write(&logbook, content.replace("pic: SELF", "pic: SELE")).unwrap();
+    let flights = &cache.flight_list().unwrap().flights;
+    assert_eq!(flights[3].name_pic, "SELE");
+    assert_eq!(cache.version(), 2);
+
+    // an error keeps the last version
+    fs::write(&logbook, content.replace("date_start", "date_stat")).unwrap();
+    assert_eq!(cache.flight_list().unwrap().flights[3].name_pic, "SELE");
+    assert!(!cache.stale_failure().unwrap().messages().is_empty());
+
+    // fixed
+    fs::write(&logbook, &content).unwrap();
+    assert_eq!(cache.flight_list().unwrap().flights[3].name_pic, "SELF");
+    assert!(cache.stale_failure().is_none());
+
+    // a default catalog created next to the logbook
+    fs::write(
+        directory.join("aircraft.yaml"),
+        "- acmodel: PA28
+  actype: SEP
+  nb_engines: 1
+  mtow: 1100
+  multipilot: false
+  cat1: light
+  cat2: none
+  cat3: none
+  immatriculations:
+    - FGHIJK
+",
+    )
+    .unwrap();
+    let flight_list = cache.flight_list().unwrap();
+    assert!(flight_list.aircrafts.get_model("FGHIJK").is_ok());
+    assert_eq!(cache.files().len(), 2);
+
+    // a flight file added to the directory of a manifest
+    let manifest = directory.join("manifest.yaml");
+    fs::create_dir_all(directory.join("flights")).unwrap();
+    fs::write(&manifest, "include:\n  - flights\n").unwrap();
+    fs::write(directory.join("flights/a.yaml"), &content).unwrap();
+    let mut cache = FlightListCache::new(&manifest.to_string_lossy(), LoadOptions::default());
+    assert_eq!(cache.flight_list().unwrap().flights.len(), 4);
+    fs::write(
+        directory.join("flights/b.yaml"),
+        "- date_start: \"2001-01-05 05:00\"
+  date_end: \"2001-01-05 06:00\"
+  apt_departure_iata: DLE
+  apt_arrival_iata: GNB
+  immatriculation: FGHIJK
+  pic: SELF
+",
+    )
+    .unwrap();
+    assert_eq!(cache.flight_list().unwrap().flights.len(), 5);
+    assert_eq!(cache.version(), 2);
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,112 @@
+use std::fmt::Display;
+
+use super::sourcemap::Position;
+
+/// Severity of a problem found in the logbook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found while loading the logbook.
+///
+/// file : the yaml file
+/// position : line and column of the key at fault, or of the flight
+/// flight_index : index of the flight in the yaml file
+/// date_start : raw start date of the flight, as written in the yaml
+/// snippet : the flight as re-emitted by StrictYamlEmitter
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: String,
+    pub position: Option<Position>,
+    pub flight_index: Option<usize>,
+    pub date_start: Option<String>,
+    pub snippet: Option<String>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{}:{}:{}: ", self.file, position.line, position.column)?,
+            None if !self.file.is_empty() => write!(f, "{}: ", self.file)?,
+            None => {}
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(flight_index) = self.flight_index {
+            write!(f, " in flight number {flight_index}")?;
+        }
+        if let Some(date_start) = &self.date_start {
+            write!(f, " ({date_start})")?;
+        }
+        write!(f, " : {}", self.message)
+    }
+}
+
+/// All the problems found while loading the logbook
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.0.push(diagnostic);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in self.0.iter() {
+            writeln!(f, "{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error naming the yaml key at fault,
+/// used as error or as context to locate the error in the file.
+/// Only the outermost FieldError of an error is used.
+#[derive(Debug)]
+pub struct FieldError {
+    pub key: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(key: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FieldError {}
@@ -1,125 +1,93 @@
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
 use time::Date;
 use time::Duration;
 use time::PrimitiveDateTime;
 use time::Time;
+
+/// Turn a string into a DateTime.
+/// Accepted formats :
+/// - 2023-03-26 14:05
+/// - 2023-03-26 14:05:30
+/// - ISO 8601 : 2023-03-26T14:05, 2023-03-26T14:05:30Z
 ///
-/// Helper functions
-/// The scanner in chronological order
-/// FindYear: expects an integer
-/// Finddayseparator: expects a -
-/// etc.
-#[derive(Debug, PartialEq)]
-enum DateScanner {
-    FindYear,
-    FindYearSeparator,
-    FindMonth,
-    FindMonthSeparator,
-    FindDay,
-    FindDaySeparator,
-    FindHour,
-    FindHourSeparator,
-    FindMinute,
-    End,
+/// The Z suffix is allowed as the dates are in UTC, other offsets are not.
+pub fn extract_date(input: &str) -> Result<PrimitiveDateTime> {
+    let trimmed = input.trim();
+    let Some((date, time)) = trimmed.split_once([' ', 'T']) else {
+        bail!("expected a date and a time like 2023-03-26 14:05, found {input:?}");
+    };
+    let date = extract_calendar_date(date).with_context(|| format!("in date {input:?}"))?;
+    let time = time.trim_start();
+    let time = match time.strip_suffix('Z') {
+        Some(time) => time,
+        None if time.contains(['+', '-']) => bail!(
+            "time offsets are not accepted in {input:?}, write the time in UTC with or without Z"
+        ),
+        None => time,
+    };
+    let time = extract_time_of_day(time).with_context(|| format!("in date {input:?}"))?;
+    Ok(PrimitiveDateTime::new(date, time))
 }
 
-/// Turn a string into a DateTime
-pub fn extract_date(input: &str) -> Result<PrimitiveDateTime> {
-    let mut scanner = DateScanner::FindYear;
-    let mut year_number = 0;
-    let mut year = 0;
-    let mut month_number = 0;
-    let mut month = 0;
-    let mut day_number: u32 = 0;
-    let mut day: u32 = 0;
-    let mut hour_number = 0;
-    let mut hour = 0;
-    let mut minute_number = 0;
-    let mut minute = 0;
+/// Turn a string YYYY-MM-DD into a Date
+pub fn extract_day(input: &str) -> Result<Date> {
+    extract_calendar_date(input.trim()).with_context(|| format!("in date {input:?}"))
+}
 
-    for (index, c) in input.char_indices() {
-        // log::trace!("index= {} char={}", index, c);
-        // log::trace!("year={}", year);
-        // log::trace!("month={}", month);
-        // log::trace!("day={}", day);
-        // log::trace!("hour={}", hour);
-        // log::trace!("minute={}", minute);
-        match c {
-            '0'..='9' => {
-                if scanner == DateScanner::FindYear {
-                    year += (c as u32 - '0' as u32) * 10_u32.pow(3 - year_number);
-                    year_number += 1;
-                    if year_number == 4 {
-                        scanner = DateScanner::FindYearSeparator;
-                    }
-                } else if scanner == DateScanner::FindMonth {
-                    month += (c as u32 - '0' as u32) * 10_u32.pow(1 - month_number);
-                    month_number += 1;
-                    if month_number == 2 {
-                        scanner = DateScanner::FindMonthSeparator;
-                    }
-                } else if scanner == DateScanner::FindDay {
-                    day += (c as u32 - '0' as u32) * 10_u32.pow(1 - day_number);
-                    day_number += 1;
-                    if day_number == 2 {
-                        scanner = DateScanner::FindDaySeparator;
-                    }
-                } else if scanner == DateScanner::FindHour {
-                    hour += (c as u32 - '0' as u32) * 10_u32.pow(1 - hour_number);
-                    hour_number += 1;
-                    if hour_number == 2 {
-                        scanner = DateScanner::FindHourSeparator;
-                    }
-                } else if scanner == DateScanner::FindMinute {
-                    minute += (c as u32 - '0' as u32) * 10_u32.pow(1 - minute_number);
-                    minute_number += 1;
-                    if minute_number == 2 {
-                        scanner = DateScanner::End;
-                    }
-                } else {
-                    bail!(
-                        "Integer found at position {} with scanner={:?}",
-                        index,
-                        scanner
-                    );
-                }
-            }
-            '-' => {
-                if scanner == DateScanner::FindYearSeparator {
-                    scanner = DateScanner::FindMonth;
-                } else if scanner == DateScanner::FindMonthSeparator {
-                    scanner = DateScanner::FindDay;
-                } else {
-                    bail!("unexpected - at position {}", index);
-                }
-            }
-            ':' => {
-                if scanner == DateScanner::FindHourSeparator {
-                    scanner = DateScanner::FindMinute;
-                } else {
-                    bail!("Unexpected : at position {}", index);
-                }
-            }
-            ' ' => {
-                if scanner == DateScanner::FindDaySeparator {
-                    scanner = DateScanner::FindHour;
-                } else {
-                    bail!("unexpected space at position {}", index);
-                }
-            }
-            c => {
-                bail!("Unexpected character {} at position {}", c, index);
-            }
-        }
+/// YYYY-MM-DD
+fn extract_calendar_date(input: &str) -> Result<Date> {
+    let parts: Vec<&str> = input.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        bail!("expected a date YYYY-MM-DD, found {input:?}");
+    };
+    let year = number(year, 4, 4, "year")?;
+    let month = number(month, 2, 2, "month")?;
+    let day = number(day, 2, 2, "day")?;
+    let month = u8::try_from(month)
+        .ok()
+        .and_then(|month| time::Month::try_from(month).ok())
+        .with_context(|| format!("month {month} out of 01..12"))?;
+    Date::from_calendar_date(year as i32, month, day as u8)
+        .with_context(|| format!("day {day} does not exist in {month} {year}"))
+}
+
+/// HH:MM or HH:MM:SS
+fn extract_time_of_day(input: &str) -> Result<Time> {
+    let parts: Vec<&str> = input.split(':').collect();
+    let (hour, minute, second) = match parts.as_slice() {
+        [hour, minute] => (hour, minute, &"00"),
+        [hour, minute, second] => (hour, minute, second),
+        _ => bail!("expected a time HH:MM or HH:MM:SS, found {input:?}"),
+    };
+    let hour = number(hour, 2, 2, "hour")?;
+    let minute = number(minute, 2, 2, "minute")?;
+    let second = number(second, 2, 2, "second")?;
+    if hour > 23 {
+        bail!("hour {hour} out of 00..23");
     }
-    if scanner != DateScanner::End {
-        bail!("scanner has not finished to parse the string. Extra characters suspected.");
+    if minute > 59 {
+        bail!("minute {minute} out of 00..59");
     }
-    let date =
-        Date::from_calendar_date(year as i32, time::Month::try_from(month as u8)?, day as u8)?;
-    let time = Time::from_hms(hour as u8, minute as u8, 0)?;
-    Ok(PrimitiveDateTime::new(date, time))
+    if second > 59 {
+        bail!("second {second} out of 00..59");
+    }
+    Ok(Time::from_hms(hour as u8, minute as u8, second as u8)?)
+}
+
+/// A number of min_digits to max_digits digits
+fn number(input: &str, min_digits: usize, max_digits: usize, what: &str) -> Result<u32> {
+    if !input.chars().all(|c| c.is_ascii_digit()) {
+        bail!("{what} {input:?} is not a number");
+    }
+    if input.len() < min_digits || input.len() > max_digits {
+        if min_digits == max_digits {
+            bail!("{what} {input:?} must have {min_digits} digits");
+        }
+        bail!("{what} {input:?} must have {min_digits} to {max_digits} digits");
+    }
+    Ok(input.parse::<u32>()?)
 }
 
 /// todo change as test
@@ -169,70 +137,101 @@ fn experiment_date() {
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum DurationScanner {
-    FindHour,
-    FindHourSeparator,
-    FindMinute,
-    End,
-}
-
+/// Turn a string into a Duration.
+/// Accepted formats :
+/// - hours and minutes : 1:05, 01:05, 105:30
+/// - 1h30, 1h05, 2h
+/// - decimal hours : 1.5, rounded to the minute
 pub fn extract_duration(input: &str) -> Result<Duration> {
-    let mut scanner = DurationScanner::FindHour;
-    let mut hour_number = 0;
-    let mut hour = 0;
-    let mut minute_number = 0;
-    let mut minute = 0;
-    for (index, c) in input.char_indices() {
-        log::trace!("index= {} char={}", index, c);
-        log::trace!("hour={}", hour);
-        log::trace!("minute={}", minute);
-        match c {
-            '0'..='9' => {
-                if scanner == DurationScanner::FindHour {
-                    hour += (c as u32 - '0' as u32) * 10_u32.pow(1 - hour_number);
-                    hour_number += 1;
-                    if hour_number == 2 {
-                        scanner = DurationScanner::FindHourSeparator;
-                    }
-                } else if scanner == DurationScanner::FindMinute {
-                    minute += (c as u32 - '0' as u32) * 10_u32.pow(1 - minute_number);
-                    minute_number += 1;
-                    if minute_number == 2 {
-                        scanner = DurationScanner::End;
-                    }
-                } else {
-                    bail!(
-                        "Integer found at position {} with scanner={:?} in {}",
-                        index,
-                        scanner,
-                        input
-                    );
-                }
-            }
-            ':' => {
-                if scanner == DurationScanner::FindHourSeparator {
-                    scanner = DurationScanner::FindMinute;
-                } else {
-                    bail!("Unexpected : at position {} in {}", index, input);
-                }
-            }
-            c => {
-                bail!(
-                    "Unexpected character {} at position {} in {}",
-                    c,
-                    index,
-                    input
-                );
-            }
+    let trimmed = input.trim();
+    let (hours, minutes) = if let Some((hours, minutes)) = trimmed.split_once(':') {
+        let minutes =
+            number(minutes, 2, 2, "minutes").with_context(|| format!("in duration {input:?}"))?;
+        (hours, minutes)
+    } else if let Some((hours, minutes)) = trimmed.split_once(['h', 'H']) {
+        let minutes = if minutes.is_empty() {
+            0
+        } else {
+            number(minutes, 1, 2, "minutes").with_context(|| format!("in duration {input:?}"))?
+        };
+        (hours, minutes)
+    } else if trimmed.contains('.') {
+        let hours = Some(trimmed)
+            .filter(|hours| hours.chars().all(|c| c.is_ascii_digit() || c == '.'))
+            .and_then(|hours| hours.parse::<f64>().ok())
+            .with_context(|| format!("{input:?} is not a number of decimal hours"))?;
+        // as the 5 digits of the hours of the other formats
+        if hours >= 100000.0 {
+            bail!("hours {hours} out of 0..99999 in duration {input:?}");
         }
+        return Ok(Duration::minutes((hours * 60.0).round() as i64));
+    } else {
+        bail!("expected a duration like 1:05, 105:30, 1h30 or 1.5, found {input:?}");
+    };
+    let hours = number(hours, 1, 5, "hours").with_context(|| format!("in duration {input:?}"))?;
+    if minutes > 59 {
+        bail!("minutes {minutes} out of 00..59 in duration {input:?}");
+    }
+    Ok(Duration::hours(hours as i64) + Duration::minutes(minutes as i64))
+}
+
+#[test]
+fn date_formats() {
+    let expected = PrimitiveDateTime::new(
+        Date::from_calendar_date(2023, time::Month::March, 26).unwrap(),
+        Time::from_hms(14, 5, 0).unwrap(),
+    );
+    for input in [
+        "2023-03-26 14:05",
+        "2023-03-26T14:05",
+        "2023-03-26T14:05:00Z",
+        "2023-03-26 14:05Z",
+        " 2023-03-26 14:05 ",
+    ] {
+        assert_eq!(extract_date(input).unwrap(), expected, "{input}");
+    }
+    assert_eq!(
+        extract_date("2023-03-26T14:05:30").unwrap(),
+        expected + Duration::seconds(30)
+    );
+    for (input, error) in [
+        ("2023-03-26", "expected a date and a time"),
+        ("2023-02-29 10:00", "day 29 does not exist"),
+        ("2023-13-01 10:00", "month 13 out of"),
+        ("2023-3-26 10:00", "month \"3\" must have 2 digits"),
+        ("2023-03-26 24:00", "hour 24 out of"),
+        ("2023-03-26 10:60", "minute 60 out of"),
+        ("2023-03-26 10:00+02:00", "time offsets are not accepted"),
+        ("2023-03-26 1O:00", "hour \"1O\" is not a number"),
+    ] {
+        let message = format!("{:#}", extract_date(input).unwrap_err());
+        assert!(message.contains(error), "{input} : {message}");
     }
-    if scanner != DurationScanner::End {
-        bail!(
-            "scanner has not finished to parse the string. Extra characters suspected in {}",
-            input
-        );
+}
+
+#[test]
+fn duration_formats() {
+    let minutes = |input| extract_duration(input).unwrap().whole_minutes();
+    assert_eq!(minutes("01:05"), 65);
+    assert_eq!(minutes("1:05"), 65);
+    assert_eq!(minutes("105:30"), 105 * 60 + 30);
+    assert_eq!(minutes("1h30"), 90);
+    assert_eq!(minutes("1h5"), 65);
+    assert_eq!(minutes("2h"), 120);
+    assert_eq!(minutes("1.5"), 90);
+    assert_eq!(minutes("0.25"), 15);
+    for (input, error) in [
+        ("1:5", "minutes \"5\" must have 2 digits"),
+        ("1:75", "minutes 75 out of"),
+        ("90", "expected a duration"),
+        ("1.5.2", "not a number of decimal hours"),
+        ("-1.5", "not a number of decimal hours"),
+        ("1.0e300", "not a number of decimal hours"),
+        ("100000.0", "hours 100000 out of 0..99999"),
+        ("ah30", "hours \"a\" is not a number"),
+        (":30", "hours \"\" must have 1 to 5 digits"),
+    ] {
+        let message = format!("{:#}", extract_duration(input).unwrap_err());
+        assert!(message.contains(error), "{input} : {message}");
     }
-    let duration = Duration::hours(hour as i64) + Duration::minutes(minute as i64);
-    Ok(duration)
 }
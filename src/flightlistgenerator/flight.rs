@@ -1,24 +1,95 @@
-use super::airport::AirportList;
+use super::aircraftnewversion::AIRCRAFT_KEYS;
+use super::airport::{AirportDetails, AirportList};
+use super::diagnostic::FieldError;
 use super::flightlist::AircraftDataBase;
 use super::flighttime::FlightDate;
 use super::flighttime::FlightTime;
+use super::flighttime::TimeBase;
 use super::flighttime::TimeOfDate;
+use super::flighttime::{
+    serialize_date, serialize_datetime, serialize_optional_datetime, serialize_optional_offset,
+};
+use super::night::night_time;
+use super::previousexperience::{PreviousExperience, PREVIOUS_EXPERIENCE_KEYS};
+use super::sourcemap::SourceLocation;
+use super::utils::format_unknown_keys;
 use super::utils::mandatory_string;
 use super::utils::optional_bool;
+use super::utils::optional_string;
+use super::utils::unknown_keys;
 use crate::flightlistgenerator::utils::mandatory_datetime;
+use crate::flightlistgenerator::utils::optional_datetime;
 use crate::flightlistgenerator::utils::optional_duration;
 use crate::flightlistgenerator::utils::optional_u8;
+use crate::flightlistgenerator::validator::validate_airborne;
 use crate::flightlistgenerator::validator::validate_date;
 use crate::flightlistgenerator::validator::validate_duration;
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 use strict_yaml_rust::StrictYaml;
 use time::Date;
-//
-//
+use time::PrimitiveDateTime;
+use time::UtcOffset;
+use time_tz::{Offset, OffsetResult, PrimitiveDateTimeExt, TimeZone, Tz};
+
+/// Keys read by Flight::flight_from_yaml,
+/// the aircraft keys are in AIRCRAFT_KEYS
+const FLIGHT_KEYS: &[&str] = &[
+    "is_sim",
+    "date_start",
+    "date_end",
+    "date_takeoff",
+    "date_landing",
+    "duration_total",
+    "duration_pic",
+    "apt_departure_iata",
+    "apt_departure_icao",
+    "apt_departure_name",
+    "apt_departure_tz",
+    "apt_departure_lat",
+    "apt_departure_lon",
+    "apt_departure_elevation",
+    "apt_departure_country",
+    "apt_arrival_iata",
+    "apt_arrival_icao",
+    "apt_arrival_name",
+    "apt_arrival_tz",
+    "apt_arrival_lat",
+    "apt_arrival_lon",
+    "apt_arrival_elevation",
+    "apt_arrival_country",
+    "pic",
+    "takeoff_day",
+    "takeoff_night",
+    "landing_day",
+    "landing_night",
+    "oc_time_ifr",
+    "oc_time_night",
+    "dual_time",
+    "instructor_time",
+    "comment",
+    "end_of_page",
+    "end_of_book",
+    "time_base",
+];
+
+/// Keys read by Flight::simulator_from_yaml
+pub const SIMULATOR_KEYS: &[&str] = &[
+    "is_sim",
+    "sim_date",
+    "sim_type",
+    "sim_total_time",
+    "comment",
+    "end_of_page",
+    "end_of_book",
+];
+
+/// All the keys of a flight or simulator yaml element
+pub const ALL_KEYS: &[&[&str]] = &[FLIGHT_KEYS, AIRCRAFT_KEYS, SIMULATOR_KEYS];
+
 // todo comment
-// todo check unknown keys in yaml
 /// Struct containing all informations about one flight.
 /// the fields are fetched from the yaml file.
 ///
@@ -26,7 +97,7 @@ use time::Date;
 /// date_start
 /// date_end
 /// todo...
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Flight {
     pub date: FlightDate,
     pub time_departure: TimeOfDate,
@@ -54,6 +125,7 @@ pub struct Flight {
     pub instructor_time: FlightTime,
 
     pub is_sim: bool,
+    #[serde(serialize_with = "serialize_date")]
     pub sim_date: Date,
     pub sim_type: String,
     pub sim_total_time_of_session: FlightTime,
@@ -61,6 +133,31 @@ pub struct Flight {
 
     pub end_of_page: bool,
     pub end_of_book: bool,
+
+    // Start and end of the flight or of the simulator session, in UTC.
+    // For a flight, the off-block and on-block times
+    #[serde(serialize_with = "serialize_datetime")]
+    pub date_start: PrimitiveDateTime,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub date_end: PrimitiveDateTime,
+    // Take-off and landing, in UTC, if logged
+    #[serde(serialize_with = "serialize_optional_datetime")]
+    pub date_takeoff: Option<PrimitiveDateTime>,
+    #[serde(serialize_with = "serialize_optional_datetime")]
+    pub date_landing: Option<PrimitiveDateTime>,
+    // From take-off to landing, if logged
+    pub airborne_time: Option<FlightTime>,
+    // UTC offsets of the departure and arrival airports, if their time zone is known
+    #[serde(serialize_with = "serialize_optional_offset")]
+    pub departure_offset: Option<UtcOffset>,
+    #[serde(serialize_with = "serialize_optional_offset")]
+    pub arrival_offset: Option<UtcOffset>,
+    // Night time computed from the airport positions, if both are known
+    pub computed_night_time: Option<FlightTime>,
+    // Great circle distance in nautical miles, if both airport positions are known
+    pub distance_nm: Option<u32>,
+    // Position in the yaml file, set by the flight list
+    pub source: SourceLocation,
 }
 
 impl Flight {
@@ -70,6 +167,10 @@ impl Flight {
         aircraft_database: &mut AircraftDataBase,
         airport_list: &mut AirportList,
     ) -> Result<Flight> {
+        let unknown = Flight::unknown_keys(yaml);
+        if !unknown.is_empty() {
+            bail!("Unknown keys : {}", format_unknown_keys(&unknown));
+        }
         let is_sim = optional_bool(yaml, "is_sim")?;
         if is_sim == Some(true) {
             Flight::simulator_from_yaml(yaml)
@@ -78,13 +179,40 @@ impl Flight {
         }
     }
 
+    /// Show the date and times in the local time of the airports,
+    /// when their time zone is known
+    pub fn to_local_time(&mut self) {
+        if let Some(departure_offset) = self.departure_offset {
+            let departure = self.date_start.assume_utc().to_offset(departure_offset);
+            self.date = FlightDate(departure.date());
+            self.time_departure = TimeOfDate(departure.time());
+        }
+        if let Some(arrival_offset) = self.arrival_offset {
+            let arrival = self.date_end.assume_utc().to_offset(arrival_offset);
+            self.time_arrival = TimeOfDate(arrival.time());
+        }
+    }
+
+    /// The keys of a flight, simulator or previous experience yaml element which are not read,
+    /// with the closest known key as suggestion
+    pub fn unknown_keys(yaml: &StrictYaml) -> Vec<(&str, Option<&'static str>)> {
+        if PreviousExperience::is_previous_experience(yaml) {
+            unknown_keys(yaml, &[PREVIOUS_EXPERIENCE_KEYS])
+        } else if matches!(optional_bool(yaml, "is_sim"), Ok(Some(true))) {
+            unknown_keys(yaml, &[SIMULATOR_KEYS])
+        } else {
+            unknown_keys(yaml, &[FLIGHT_KEYS, AIRCRAFT_KEYS])
+        }
+    }
+
     fn flight_from_yaml(
         yaml: &StrictYaml,
         aircraft_database: &mut AircraftDataBase,
         airport_list: &mut AirportList,
     ) -> Result<Flight> {
-        // [date_start]
+        // [date_start] [date_end], in the time base of the flight
         let date_start = mandatory_datetime(yaml, "date_start")?;
+        let date_end = mandatory_datetime(yaml, "date_end")?;
 
         // [apt_departure_iata]
         let airport_departure = airport_list
@@ -93,17 +221,14 @@ impl Flight {
                 &yaml["apt_departure_icao"],
                 &yaml["apt_departure_name"],
             )
-            .context("Problem with airport departure")?;
-
-        // [date_end]
-        let date_end = mandatory_datetime(yaml, "date_end")?;
-
-        let date = FlightDate(date_start.date());
-        let time_departure = TimeOfDate(date_start.time());
-        let time_arrival = TimeOfDate(date_end.time());
-        // Quick date validation
-        validate_date(&date_start, &date_end)
-            .context("validation : fields [date_start] and [date_end]")?;
+            .with_context(|| {
+                FieldError::new(
+                    airport_key(yaml, "apt_departure"),
+                    "Problem with airport departure",
+                )
+            })?;
+        let departure_details = AirportDetails::from_yaml(yaml, "apt_departure_")?;
+        airport_list.add_details(&airport_departure, departure_details, "apt_departure_")?;
 
         // [apt_arrival_???]
         let airport_arrival = airport_list
@@ -112,12 +237,81 @@ impl Flight {
                 &yaml["apt_arrival_icao"],
                 &yaml["apt_arrival_name"],
             )
-            .context("Problem with airport arrival")?;
+            .with_context(|| {
+                FieldError::new(
+                    airport_key(yaml, "apt_arrival"),
+                    "Problem with airport arrival",
+                )
+            })?;
+        let arrival_details = AirportDetails::from_yaml(yaml, "apt_arrival_")?;
+        airport_list.add_details(&airport_arrival, arrival_details, "apt_arrival_")?;
+
+        // [time_base] : the dates are stored in UTC
+        let departure_tz = airport_list.time_zone(&airport_departure);
+        let arrival_tz = airport_list.time_zone(&airport_arrival);
+        let time_base = optional_time_base(yaml)?;
+        let (date_start, date_end) = match time_base {
+            TimeBase::Utc => (date_start, date_end),
+            TimeBase::Local => (
+                local_to_utc(date_start, departure_tz, &airport_departure)
+                    .with_context(|| FieldError::new("date_start", "local departure time"))?,
+                local_to_utc(date_end, arrival_tz, &airport_arrival)
+                    .with_context(|| FieldError::new("date_end", "local arrival time"))?,
+            ),
+        };
+        let departure_offset = departure_tz.map(|tz| utc_offset(tz, date_start));
+        let arrival_offset = arrival_tz.map(|tz| utc_offset(tz, date_end));
+
+        let date = FlightDate(date_start.date());
+        let time_departure = TimeOfDate(date_start.time());
+        let time_arrival = TimeOfDate(date_end.time());
+        // Quick date validation
+        validate_date(&date_start, &date_end).with_context(|| {
+            FieldError::new(
+                "date_end",
+                "validation : fields [date_start] and [date_end]",
+            )
+        })?;
+
+        // [date_takeoff] [date_landing] : date_start and date_end are then
+        // the off-block and on-block times
+        let airborne = match (
+            optional_datetime(yaml, "date_takeoff")?,
+            optional_datetime(yaml, "date_landing")?,
+        ) {
+            (Some(takeoff), Some(landing)) => Some(match time_base {
+                TimeBase::Utc => (takeoff, landing),
+                TimeBase::Local => (
+                    local_to_utc(takeoff, departure_tz, &airport_departure)
+                        .with_context(|| FieldError::new("date_takeoff", "local take-off time"))?,
+                    local_to_utc(landing, arrival_tz, &airport_arrival)
+                        .with_context(|| FieldError::new("date_landing", "local landing time"))?,
+                ),
+            }),
+            (None, None) => None,
+            (Some(_), None) => bail!(FieldError::new(
+                "date_takeoff",
+                "[date_landing] missing with [date_takeoff]"
+            )),
+            (None, Some(_)) => bail!(FieldError::new(
+                "date_landing",
+                "[date_takeoff] missing with [date_landing]"
+            )),
+        };
+        if let Some((takeoff, landing)) = airborne {
+            validate_airborne(&date_start, &takeoff, &landing, &date_end)?;
+        }
+        let date_takeoff = airborne.map(|(takeoff, _)| takeoff);
+        let date_landing = airborne.map(|(_, landing)| landing);
+        let airborne_time = airborne.map(|(takeoff, landing)| FlightTime(landing - takeoff));
 
         // acmodel immatriculation
-        let (immatriculation, acmodel) = aircraft_database
-            .add_flight(yaml)
-            .context("Function AircraftDatabse.add_flight returned an error")?;
+        let (immatriculation, acmodel) = aircraft_database.add_flight(yaml).with_context(|| {
+            FieldError::new(
+                "immatriculation",
+                "Function AircraftDatabse.add_flight returned an error",
+            )
+        })?;
         let immatriculation = immatriculation.to_string();
         let acmodel = acmodel.to_string();
 
@@ -129,8 +323,9 @@ impl Flight {
         // [duration_total]
         let total_flight_time = match optional_duration(yaml, "duration_total")? {
             Some(duration_total) => {
-                validate_duration(duration_total, &date_start, &date_end)
-                    .context("validation : field [duration_total]")?;
+                validate_duration(duration_total, &date_start, &date_end).with_context(|| {
+                    FieldError::new("duration_total", "validation : field [duration_total]")
+                })?;
                 FlightTime(duration_total)
             }
             None => FlightTime(date_end - date_start),
@@ -164,17 +359,30 @@ impl Flight {
             None => FlightTime::ZERO,
         };
 
-        // [oc_time_night]
+        // computed from the airport positions
+        let positions = airport_list
+            .coordinates(&airport_departure)
+            .zip(airport_list.coordinates(&airport_arrival));
+        let distance_nm =
+            positions.map(|(departure, arrival)| departure.distance_nm(&arrival).round() as u32);
+
+        // [oc_time_night] : computed from civil twilight when absent
+        let computed_night_time = positions.map(|(departure, arrival)| {
+            FlightTime(
+                night_time(&departure, &arrival, date_start, date_end).min(*total_flight_time),
+            )
+        });
         let operational_condition_time_night = match optional_duration(yaml, "oc_time_night")? {
             Some(oc_time_night) => FlightTime(oc_time_night),
-            None => FlightTime::ZERO,
+            None => computed_night_time.unwrap_or(FlightTime::ZERO),
         };
 
         // [duration_pic]
         let pilot_in_command_time = match optional_duration(yaml, "duration_pic")? {
             Some(duration_pic) => {
-                validate_duration(duration_pic, &date_start, &date_end)
-                    .context("validation : field [duration_pic]")?;
+                validate_duration(duration_pic, &date_start, &date_end).with_context(|| {
+                    FieldError::new("duration_pic", "validation : field [duration_pic]")
+                })?;
                 FlightTime(duration_pic)
             }
             None => FlightTime::ZERO,
@@ -206,7 +414,7 @@ impl Flight {
             StrictYaml::String(s) => s.to_string(),
             StrictYaml::BadValue => "".to_string(),
             _ => {
-                bail!(format!("bad comment value"));
+                bail!(FieldError::new("comment", "bad comment value"));
             }
         };
         let end_of_page = optional_bool(yaml, "end_of_page")?.unwrap_or(false);
@@ -249,6 +457,17 @@ impl Flight {
 
             end_of_page,
             end_of_book,
+
+            date_start,
+            date_end,
+            date_takeoff,
+            date_landing,
+            airborne_time,
+            departure_offset,
+            arrival_offset,
+            computed_night_time,
+            distance_nm,
+            source: SourceLocation::default(),
         };
         Ok(flight)
     }
@@ -259,14 +478,19 @@ impl Flight {
         let sim_date = sim_datetime.date();
         let sim_type = mandatory_string(yaml, "sim_type")?.to_string();
         let tmp_sim_total_time_of_session = optional_duration(yaml, "sim_total_time")?
-            .context("[sim_total_time] missing or other problem.")?;
+            .with_context(|| {
+                FieldError::new(
+                    "sim_total_time",
+                    "[sim_total_time] missing or other problem.",
+                )
+            })?;
         let sim_total_time_of_session = FlightTime(tmp_sim_total_time_of_session);
         // [remark]
         let remark = match &yaml["comment"] {
             StrictYaml::String(s) => s.to_string(),
             StrictYaml::BadValue => "".to_string(),
             _ => {
-                bail!(format!("bad comment value"));
+                bail!(FieldError::new("comment", "bad comment value"));
             }
         };
         let end_of_page = optional_bool(yaml, "end_of_page")?.unwrap_or(false);
@@ -303,7 +527,184 @@ impl Flight {
 
             end_of_page,
             end_of_book,
+
+            date_start: sim_datetime,
+            date_end: sim_datetime + tmp_sim_total_time_of_session,
+            date_takeoff: None,
+            date_landing: None,
+            airborne_time: None,
+            departure_offset: None,
+            arrival_offset: None,
+            computed_night_time: None,
+            distance_nm: None,
+            source: SourceLocation::default(),
         };
         Ok(flight)
     }
 }
+
+/// [time_base] : utc by default
+fn optional_time_base(yaml: &StrictYaml) -> Result<TimeBase> {
+    match optional_string(yaml, "time_base") {
+        Some(time_base) => TimeBase::from_name(time_base)
+            .with_context(|| FieldError::new("time_base", "in field : [time_base]")),
+        None => Ok(TimeBase::Utc),
+    }
+}
+
+/// Convert a local time of an airport to UTC.
+/// A local time skipped or repeated by a daylight saving change is an error.
+fn local_to_utc(
+    local: PrimitiveDateTime,
+    tz: Option<&Tz>,
+    airport: &str,
+) -> Result<PrimitiveDateTime> {
+    let tz = tz.with_context(|| {
+        format!("[time_base] local needs the time zone of airport {airport}, add tz to the airport catalog")
+    })?;
+    let utc = match local.assume_timezone(tz) {
+        OffsetResult::Some(local) => local.to_offset(UtcOffset::UTC),
+        OffsetResult::Ambiguous(_, _) => {
+            bail!("local time {local} is ambiguous in {}, use UTC", tz.name())
+        }
+        OffsetResult::None => bail!("local time {local} does not exist in {}", tz.name()),
+    };
+    Ok(PrimitiveDateTime::new(utc.date(), utc.time()))
+}
+
+/// UTC offset of a time zone at a UTC date
+fn utc_offset(tz: &Tz, utc: PrimitiveDateTime) -> UtcOffset {
+    tz.get_offset_utc(&utc.assume_utc()).to_utc()
+}
+
+/// The first airport key present in the yaml, to locate airport errors
+/// prefix : apt_departure or apt_arrival
+fn airport_key(yaml: &StrictYaml, prefix: &str) -> &'static str {
+    let keys: [&'static str; 3] = if prefix == "apt_departure" {
+        [
+            "apt_departure_iata",
+            "apt_departure_icao",
+            "apt_departure_name",
+        ]
+    } else {
+        ["apt_arrival_iata", "apt_arrival_icao", "apt_arrival_name"]
+    };
+    keys.into_iter()
+        .find(|key| !yaml[*key].is_badvalue())
+        .unwrap_or(keys[0])
+}
+
+#[test]
+fn local_times_to_utc() {
+    let paris = time_tz::timezones::get_by_name("Europe/Paris").unwrap();
+    let local = |input| super::extracttime::extract_date(input).unwrap();
+    // daylight saving time starts at 02:00 on 2023-03-26
+    let start = local_to_utc(local("2023-03-26 01:30"), Some(paris), "GNB").unwrap();
+    let end = local_to_utc(local("2023-03-26 03:30"), Some(paris), "GNB").unwrap();
+    assert_eq!(start, local("2023-03-26 00:30"));
+    assert_eq!(end - start, time::Duration::hours(1));
+    assert_eq!(
+        utc_offset(paris, end),
+        UtcOffset::from_hms(2, 0, 0).unwrap()
+    );
+    // skipped and repeated local times
+    assert!(local_to_utc(local("2023-03-26 02:30"), Some(paris), "GNB").is_err());
+    assert!(local_to_utc(local("2023-10-29 02:30"), Some(paris), "GNB").is_err());
+    // no time zone
+    assert!(local_to_utc(local("2023-03-26 01:30"), None, "GNB").is_err());
+}
+
+#[test]
+fn takeoff_and_landing() {
+    use strict_yaml_rust::StrictYamlLoader;
+
+    // the block times, then the take-off and landing
+    let flight = |dates: &str| {
+        let yaml = format!(
+            "apt_departure_iata: GNB
+apt_departure_icao: LFLS
+apt_departure_name: Grenoble
+apt_departure_tz: Europe/Paris
+apt_arrival_iata: DLE
+apt_arrival_icao: LFGJ
+apt_arrival_name: Dole
+apt_arrival_tz: Europe/Paris
+immatriculation: ABCDEF
+acmodel: C172
+actype: SEP
+nb_engines: 1
+mtow: 1200
+multipilot: false
+cat1: light
+cat2: none
+cat3: none
+pic: SELF
+{dates}"
+        );
+        let yaml = &StrictYamlLoader::load_from_str(&yaml).unwrap()[0];
+        Flight::from_yaml(
+            yaml,
+            &mut AircraftDataBase::empty(),
+            &mut AirportList::new(),
+        )
+    };
+    let block = |airborne: &str| {
+        flight(&format!(
+            "date_start: \"2001-01-01 05:00\"\ndate_end: \"2001-01-01 06:00\"\n{airborne}"
+        ))
+    };
+    let error = |airborne: &str| {
+        let error = block(airborne).unwrap_err();
+        let field = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<FieldError>())
+            .unwrap();
+        (field.key.clone(), field.message.clone())
+    };
+    let date = |input| super::extracttime::extract_date(input).unwrap();
+
+    let logged =
+        block("date_takeoff: \"2001-01-01 05:10\"\ndate_landing: \"2001-01-01 05:50\"").unwrap();
+    assert_eq!(logged.date_takeoff, Some(date("2001-01-01 05:10")));
+    assert_eq!(logged.date_landing, Some(date("2001-01-01 05:50")));
+    assert_eq!(logged.airborne_time.unwrap().0.whole_minutes(), 40);
+    assert!(block("").unwrap().airborne_time.is_none());
+
+    // both or none
+    assert_eq!(
+        error("date_takeoff: \"2001-01-01 05:10\"").0,
+        "date_takeoff"
+    );
+    assert_eq!(
+        error("date_landing: \"2001-01-01 05:50\"").0,
+        "date_landing"
+    );
+
+    // off-block <= take-off < landing <= on-block
+    let (key, message) =
+        error("date_takeoff: \"2001-01-01 04:50\"\ndate_landing: \"2001-01-01 05:50\"");
+    assert_eq!(key, "date_takeoff");
+    assert!(message.contains("before off-block"), "{message}");
+    let (key, message) =
+        error("date_takeoff: \"2001-01-01 05:30\"\ndate_landing: \"2001-01-01 05:30\"");
+    assert_eq!(key, "date_landing");
+    assert!(message.contains("not after take-off"), "{message}");
+    let (key, message) =
+        error("date_takeoff: \"2001-01-01 05:10\"\ndate_landing: \"2001-01-01 06:10\"");
+    assert_eq!(key, "date_landing");
+    assert!(message.contains("after on-block"), "{message}");
+
+    // local times of the airports, UTC+1 in winter
+    let local = flight(
+        "time_base: local
+date_start: \"2001-01-01 06:00\"
+date_end: \"2001-01-01 07:00\"
+date_takeoff: \"2001-01-01 06:10\"
+date_landing: \"2001-01-01 06:50\"",
+    )
+    .unwrap();
+    assert_eq!(local.date_start, date("2001-01-01 05:00"));
+    assert_eq!(local.date_takeoff, Some(date("2001-01-01 05:10")));
+    assert_eq!(local.date_landing, Some(date("2001-01-01 05:50")));
+    assert_eq!(local.airborne_time.unwrap().0.whole_minutes(), 40);
+}
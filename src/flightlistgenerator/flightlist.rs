@@ -1,11 +1,23 @@
-use std::{fmt::Display, fs::read_to_string, path::Path};
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
-use strict_yaml_rust::{StrictYamlEmitter, StrictYamlLoader};
+use anyhow::{bail, Context, Result};
+use strict_yaml_rust::{StrictYaml, StrictYamlEmitter, StrictYamlLoader};
 
 use crate::renderable::filters::FlightFilter;
 
 use super::airport::AirportList;
+use super::airportreference::AirportReference;
+use super::diagnostic::{Diagnostic, Diagnostics, FieldError, Severity};
+use super::manifest::Manifest;
+use super::options::LoadOptions;
+use super::previousexperience::PreviousExperience;
+use super::sourcemap::{Position, SourceLocation, SourceMap};
+use super::utils::format_unknown_key;
+use super::validator::{check_flight_times, perform_basic_check};
 pub(crate) use super::{aircraftnewversion::AircraftDataBase, flight::Flight};
 
 /// Struct to parse the yaml file
@@ -15,70 +27,370 @@ pub(crate) use super::{aircraftnewversion::AircraftDataBase, flight::Flight};
 /// content : a vector of flights
 /// airports : a list of all the airports
 /// aircrafts : a list of aircrafts
+#[derive(Clone)]
 pub struct FlightList {
     pub flights: Vec<Flight>,
+    // totals brought forward from previous logbooks
+    pub previous_experience: Vec<PreviousExperience>,
     pub airports: AirportList,
     pub aircrafts: AircraftDataBase,
+    /// the files read : the logbook, its flight files and catalogs
+    pub files: Vec<PathBuf>,
+    /// the manifest of the logbook, if it is split across several files
+    pub manifest: Option<Manifest>,
 }
 
 impl FlightList {
     /// constructs a Flights struct from a yaml file
+    /// returns an error listing all the problems of the logbook
     pub fn load_from_yaml<P: AsRef<Path> + Display>(p: P) -> Result<Self> {
-        log::info!("Opening file {}", p);
-        //
-        // Load the yaml file, take the first yaml document
-        let file_content =
-            read_to_string(&p).with_context(|| format!("Cannot open file : {}", &p))?;
-        let yaml_documents = StrictYamlLoader::load_from_str(&file_content)?;
-        let yaml_document = yaml_documents
-            .get(0)
-            .with_context(|| format!("Cannot find the first document in file : {}", &p))?;
-
-        // Iterate over all the flights and add the flights to the flight list
-        let mut flights = Vec::new();
-        let mut airports = AirportList::new();
-        let mut aircrafts = AircraftDataBase::empty();
-        for (index, flight) in yaml_document
-            .as_vec()
-            .with_context(|| {
-                format!(
-                    "The first yaml document should be a vector of flights in file : {}",
-                    &p
-                )
-            })?
-            .iter()
-            .enumerate()
-        {
-            let flight =
-                Flight::from_yaml(flight, &mut aircrafts, &mut airports).with_context(|| {
-                    let mut formatted_yaml = String::new();
-                    let mut emitter = StrictYamlEmitter::new(&mut formatted_yaml);
-                    emitter.compact(true);
-                    emitter.dump(flight).unwrap();
-                    format!("In flight number {index} {formatted_yaml}")
+        let (flight_list, diagnostics) =
+            FlightList::load_with_diagnostics(&p, &LoadOptions::default())?;
+        if diagnostics.has_errors() {
+            bail!("Errors in file {} :\n{}", &p, diagnostics);
+        }
+        Ok(flight_list)
+    }
+
+    /// constructs a Flights struct from a yaml file, without stopping
+    /// at the first bad flight.
+    /// The yaml file is a vector of flights, or a manifest including
+    /// several flight files (see Manifest).
+    /// The aircraft and airport catalogs are loaded first, if any.
+    /// The flights with errors are left out of the flight list
+    /// and reported in the diagnostics, with their position in the file.
+    /// A yaml syntax error gives an empty flight list.
+    /// Each flight is checked against the validation rules of the options.
+    /// Returns an error only if a file itself cannot be read.
+    pub fn load_with_diagnostics<P: AsRef<Path> + Display>(
+        p: P,
+        options: &LoadOptions,
+    ) -> Result<(Self, Diagnostics)> {
+        let mut flight_list = FlightList::empty();
+        let mut diagnostics = Diagnostics::default();
+
+        // The logbook is a vector of flights,
+        // or a manifest listing the flight files
+        let file = p.to_string();
+        flight_list.files.push(PathBuf::from(&file));
+        let logbook = read_yaml(&file, &mut diagnostics)?;
+        let manifest = match &logbook {
+            Some((yaml @ StrictYaml::Hash(_), _)) => match Manifest::from_yaml(yaml, p.as_ref()) {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    diagnostics.push(file_diagnostic(Severity::Error, &file, format!("{e:#}")));
+                    return Ok((flight_list, diagnostics));
+                }
+            },
+            _ => None,
+        };
+
+        // Catalogs, before the flights
+        flight_list.load_catalogs(p.as_ref(), manifest.as_ref(), options, &mut diagnostics)?;
+
+        // Flights
+        match (&manifest, logbook) {
+            (Some(manifest), _) => {
+                for pattern in manifest.empty_patterns.iter() {
+                    diagnostics.push(file_diagnostic(
+                        Severity::Warning,
+                        &file,
+                        format!("include {pattern} matches no file"),
+                    ));
+                }
+                // the flight files are concatenated in order
+                for flight_file in manifest.flight_files.iter() {
+                    flight_list.files.push(flight_file.clone());
+                    let flight_file = flight_file.to_string_lossy();
+                    match read_yaml_vector(&flight_file, &mut diagnostics) {
+                        Ok(Some((flights, source_map))) => flight_list.add_flights(
+                            &flight_file,
+                            &flights,
+                            &source_map,
+                            options,
+                            &mut diagnostics,
+                        ),
+                        Ok(None) => {}
+                        Err(e) => diagnostics.push(file_diagnostic(
+                            Severity::Error,
+                            &flight_file,
+                            format!("{e:#}"),
+                        )),
+                    }
+                }
+            }
+            (None, Some((yaml, source_map))) => {
+                let flights = yaml.into_vec().with_context(|| {
+                    format!("The first yaml document should be a vector of flights or a manifest in file : {file}")
                 })?;
-            flights.push(flight);
+                flight_list.add_flights(&file, &flights, &source_map, options, &mut diagnostics);
+            }
+            (None, None) => {}
+        }
+
+        flight_list.manifest = manifest;
+
+        // Checks across the whole flight list
+        for diagnostic in perform_basic_check(&flight_list) {
+            diagnostics.push(diagnostic);
+        }
+        log::info!("{} flights added to Flights", flight_list.flights.len());
+        Ok((flight_list, diagnostics))
+    }
+
+    /// A flight list without flights, with the catalogs of a logbook
+    /// which does not exist yet
+    pub fn with_catalogs<P: AsRef<Path>>(
+        p: P,
+        options: &LoadOptions,
+    ) -> Result<(Self, Diagnostics)> {
+        let mut flight_list = FlightList::empty();
+        let mut diagnostics = Diagnostics::default();
+        flight_list.load_catalogs(p.as_ref(), None, options, &mut diagnostics)?;
+        Ok((flight_list, diagnostics))
+    }
+
+    /// The files a load of the logbook reads, in the order of files :
+    /// the flight files of the manifest are listed again and the default
+    /// catalogs looked for again, without reading the logbook
+    pub fn logbook_files(
+        logbook: &Path,
+        manifest: Option<&Manifest>,
+        options: &LoadOptions,
+    ) -> Vec<PathBuf> {
+        let mut files = vec![logbook.to_path_buf()];
+        files.extend(options.airport_data(
+            logbook,
+            manifest.and_then(|manifest| manifest.airport_data_file.as_deref()),
+        ));
+        files.extend(options.aircraft_catalog(
+            logbook,
+            manifest.and_then(|manifest| manifest.aircraft_catalog_file.as_deref()),
+        ));
+        files.extend(options.airport_catalog(
+            logbook,
+            manifest.and_then(|manifest| manifest.airport_catalog_file.as_deref()),
+        ));
+        if let Some(manifest) = manifest {
+            files.extend(manifest.list_flight_files());
+        }
+        files
+    }
+
+    /// Load the airport reference data and the aircraft and airport catalogs of the logbook,
+    /// the errors go to the diagnostics
+    fn load_catalogs(
+        &mut self,
+        logbook: &Path,
+        manifest: Option<&Manifest>,
+        options: &LoadOptions,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        // the reference data first, to check the codes of the catalog
+        let manifest_airport_data =
+            manifest.and_then(|manifest| manifest.airport_data_file.as_deref());
+        if let Some(file) = options.airport_data(logbook, manifest_airport_data) {
+            self.files.push(file.clone());
+            match AirportReference::from_csv_file(&file, diagnostics) {
+                Ok(reference) => self.airports.set_reference(reference),
+                Err(e) => diagnostics.push(file_diagnostic(
+                    Severity::Error,
+                    &file.to_string_lossy(),
+                    format!("{e:#}"),
+                )),
+            }
+        }
+        let manifest_aircraft_catalog =
+            manifest.and_then(|manifest| manifest.aircraft_catalog_file.as_deref());
+        if let Some(catalog) = options.aircraft_catalog(logbook, manifest_aircraft_catalog) {
+            self.files.push(catalog.clone());
+            load_catalog(&catalog.to_string_lossy(), diagnostics, |yaml| {
+                self.aircrafts.add_catalog_entry(yaml)
+            })?;
+        }
+        let manifest_airport_catalog =
+            manifest.and_then(|manifest| manifest.airport_catalog_file.as_deref());
+        if let Some(catalog) = options.airport_catalog(logbook, manifest_airport_catalog) {
+            self.files.push(catalog.clone());
+            load_catalog(&catalog.to_string_lossy(), diagnostics, |yaml| {
+                self.airports.add_catalog_entry(yaml)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Add the flights of a yaml vector, the errors go to the diagnostics
+    fn add_flights(
+        &mut self,
+        file: &str,
+        flights: &[StrictYaml],
+        source_map: &SourceMap,
+        options: &LoadOptions,
+        diagnostics: &mut Diagnostics,
+    ) {
+        for (index, flight) in flights.iter().enumerate() {
+            self.add_flight(
+                flight,
+                file,
+                index,
+                |key| source_map.position(index, key),
+                options,
+                diagnostics,
+            );
+        }
+    }
+
+    /// Add a flight which does not come from a yaml file, like an imported row.
+    /// All the diagnostics of the flight are located at position.
+    /// Returns true if the flight has been added.
+    pub fn add_imported_flight(
+        &mut self,
+        flight: &StrictYaml,
+        file: &str,
+        index: usize,
+        position: Position,
+        options: &LoadOptions,
+        diagnostics: &mut Diagnostics,
+    ) -> bool {
+        self.add_flight(
+            flight,
+            file,
+            index,
+            |_| Some(position),
+            options,
+            diagnostics,
+        )
+    }
+
+    /// Add one flight, the errors go to the diagnostics.
+    /// position gives the position of a key of the flight,
+    /// or of the flight itself.
+    /// Returns true if the flight has been added.
+    fn add_flight(
+        &mut self,
+        flight: &StrictYaml,
+        file: &str,
+        index: usize,
+        position: impl Fn(Option<&str>) -> Option<Position>,
+        options: &LoadOptions,
+        diagnostics: &mut Diagnostics,
+    ) -> bool {
+        let date_start = flight["date_start"]
+            .as_str()
+            .or_else(|| flight["sim_date"].as_str())
+            .map(|date| date.to_string());
+        // One diagnostic for each unknown key
+        let unknown_keys = Flight::unknown_keys(flight);
+        for (key, suggestion) in unknown_keys.iter() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format_unknown_key(key, *suggestion),
+                file: file.to_string(),
+                position: position(Some(key)),
+                flight_index: Some(index),
+                date_start: date_start.clone(),
+                snippet: Some(yaml_snippet(flight)),
+            });
+        }
+        if !unknown_keys.is_empty() {
+            return false;
+        }
+        if PreviousExperience::is_previous_experience(flight) {
+            return match PreviousExperience::from_yaml(flight) {
+                Ok(mut previous_experience) => {
+                    previous_experience.source = SourceLocation {
+                        file: file.to_string(),
+                        index,
+                        position: position(None),
+                    };
+                    self.previous_experience.push(previous_experience);
+                    true
+                }
+                Err(e) => {
+                    let date = flight["date"].as_str().map(|date| date.to_string());
+                    diagnostics.push(error_diagnostic(&e, file, position, index, date, flight));
+                    false
+                }
+            };
+        }
+        match Flight::from_yaml(flight, &mut self.aircrafts, &mut self.airports) {
+            Ok(mut flight) => {
+                for finding in check_flight_times(&flight, &options.rules) {
+                    diagnostics.push(Diagnostic {
+                        severity: finding.severity,
+                        message: finding.message,
+                        file: file.to_string(),
+                        position: position(Some(finding.key)),
+                        flight_index: Some(index),
+                        date_start: date_start.clone(),
+                        snippet: None,
+                    });
+                }
+                flight.source = SourceLocation {
+                    file: file.to_string(),
+                    index,
+                    position: position(None),
+                };
+                self.flights.push(flight);
+                true
+            }
+            Err(e) => {
+                diagnostics.push(error_diagnostic(
+                    &e, file, position, index, date_start, flight,
+                ));
+                false
+            }
+        }
+    }
+
+    fn empty() -> Self {
+        FlightList {
+            flights: Vec::new(),
+            previous_experience: Vec::new(),
+            airports: AirportList::new(),
+            aircrafts: AircraftDataBase::empty(),
+            files: Vec::new(),
+            manifest: None,
         }
-        log::info!("{} flights added to Flights", flights.len());
-        Ok(FlightList {
-            flights,
-            airports,
-            aircrafts,
-        })
     }
 
     pub fn iter_flights_without_sims(&self) -> impl Iterator<Item = &Flight> {
         self.flights.iter().filter(|f| !f.is_sim)
     }
 
+    /// Sum of the previous experience entries
+    pub fn previous_experience_total(&self) -> PreviousExperience {
+        let mut total = PreviousExperience::default();
+        for previous in self.previous_experience.iter() {
+            total.add(previous);
+        }
+        total
+    }
+
+    /// Keep the flights matching the filter. The previous experience
+    /// is kept by the date filters when its date matches, and removed
+    /// by the aircraft filters.
     pub fn filter(&mut self, filter: &FlightFilter) {
         if filter.flights_before_enabled() {
             self.flights
                 .retain(|flight| flight.date <= filter.flights_before());
+            self.previous_experience.retain(|previous| {
+                previous
+                    .date
+                    .is_none_or(|date| date <= filter.flights_before().0)
+            });
         }
         if filter.flights_after_enabled() {
             self.flights
                 .retain(|flight| flight.date >= filter.flights_after());
+            self.previous_experience.retain(|previous| {
+                previous
+                    .date
+                    .is_some_and(|date| date >= filter.flights_after().0)
+            });
+        }
+        if filter.aircraft_model_enabled() || filter.aircraft_family_enabled() {
+            self.previous_experience.clear();
         }
         if filter.aircraft_model_enabled() {
             self.flights.retain(|flight| {
@@ -87,37 +399,192 @@ impl FlightList {
                         .aircrafts
                         .get_model_name(&flight.immatriculation)
                         .unwrap();
-                    if model == filter.aircraft_model() {
-                        true
-                    } else {
-                        false
-                    }
+                    model == filter.aircraft_model()
                 }
                 // if
                 else {
                     false
                 }
             });
-	}
-	if filter.aircraft_family_enabled() {
+        }
+        if filter.aircraft_family_enabled() {
             self.flights.retain(|flight| {
                 if !flight.is_sim {
                     let family = self
                         .aircrafts
                         .get_family_name(&flight.immatriculation)
                         .unwrap();
-                    if family == filter.aircraft_family() {
-                        true
-                    } else {
-                        false
-                    }
+                    family == filter.aircraft_family()
                 }
                 // if
                 else {
                     false
                 }
             });
-	}
+        }
+    }
+}
+
+/// The yaml element re-emitted in compact form
+fn yaml_snippet(yaml: &StrictYaml) -> String {
+    let mut formatted_yaml = String::new();
+    let mut emitter = StrictYamlEmitter::new(&mut formatted_yaml);
+    emitter.compact(true);
+    emitter.dump(yaml).unwrap();
+    formatted_yaml
+}
+
+/// Diagnostic of an error on an element of a yaml vector,
+/// located at the key of the FieldError of the error if any
+fn error_diagnostic(
+    e: &anyhow::Error,
+    file: &str,
+    position: impl Fn(Option<&str>) -> Option<Position>,
+    index: usize,
+    date_start: Option<String>,
+    yaml: &StrictYaml,
+) -> Diagnostic {
+    let key = e.downcast_ref::<FieldError>().map(|e| e.key.as_str());
+    Diagnostic {
+        severity: Severity::Error,
+        message: format!("{e:#}"),
+        file: file.to_string(),
+        position: position(key),
+        flight_index: Some(index),
+        date_start,
+        snippet: Some(yaml_snippet(yaml)),
+    }
+}
+
+/// Read the first document of a yaml file.
+/// A yaml syntax error is added to the diagnostics and gives None.
+fn read_yaml(file: &str, diagnostics: &mut Diagnostics) -> Result<Option<(StrictYaml, SourceMap)>> {
+    log::info!("Opening file {}", file);
+    let file_content =
+        read_to_string(file).with_context(|| format!("Cannot open file : {file}"))?;
+    let yaml_documents = match StrictYamlLoader::load_from_str(&file_content) {
+        Ok(yaml_documents) => yaml_documents,
+        Err(e) => {
+            diagnostics.push(Diagnostic {
+                position: Some(Position::from(e.marker())),
+                ..file_diagnostic(Severity::Error, file, e.to_string())
+            });
+            return Ok(None);
+        }
+    };
+    let yaml_document = yaml_documents
+        .into_iter()
+        .next()
+        .with_context(|| format!("Cannot find the first document in file : {file}"))?;
+    Ok(Some((
+        yaml_document,
+        SourceMap::from_yaml_str(&file_content),
+    )))
+}
+
+/// Read a yaml file whose first document is a vector.
+/// A yaml syntax error is added to the diagnostics and gives None.
+fn read_yaml_vector(
+    file: &str,
+    diagnostics: &mut Diagnostics,
+) -> Result<Option<(Vec<StrictYaml>, SourceMap)>> {
+    match read_yaml(file, diagnostics)? {
+        Some((yaml_document, source_map)) => {
+            let elements = yaml_document.into_vec().with_context(|| {
+                format!("The first yaml document should be a vector in file : {file}")
+            })?;
+            Ok(Some((elements, source_map)))
+        }
+        None => Ok(None),
     }
 }
 
+/// Diagnostic about a whole file
+fn file_diagnostic(severity: Severity, file: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        severity,
+        message,
+        file: file.to_string(),
+        position: None,
+        flight_index: None,
+        date_start: None,
+        snippet: None,
+    }
+}
+
+/// Load a catalog : a yaml vector of aircraft models or airports.
+/// The errors of each element go to the diagnostics.
+fn load_catalog(
+    file: &str,
+    diagnostics: &mut Diagnostics,
+    mut add_entry: impl FnMut(&StrictYaml) -> Result<()>,
+) -> Result<()> {
+    if let Some((elements, source_map)) = read_yaml_vector(file, diagnostics)? {
+        for (index, element) in elements.iter().enumerate() {
+            if let Err(e) = add_entry(element) {
+                let mut diagnostic = error_diagnostic(
+                    &e,
+                    file,
+                    |key| source_map.position(index, key),
+                    index,
+                    None,
+                    element,
+                );
+                // not a flight
+                diagnostic.flight_index = None;
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn all_bad_flights_reported() {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests/errors.yaml");
+    let (flight_list, diagnostics) =
+        FlightList::load_with_diagnostics(file, &LoadOptions::default()).unwrap();
+    // the good flight is kept
+    assert_eq!(flight_list.flights.len(), 1);
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| {
+            (
+                diagnostic.file.as_str(),
+                diagnostic.position.map(|position| position.line),
+                diagnostic.flight_index,
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [(file, Some(30), Some(1)), (file, Some(34), Some(2))]
+    );
+}
+
+#[test]
+fn catalogs_and_redefinitions() {
+    let file = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/catalogs/logbook.yaml"
+    );
+    let (flight_list, diagnostics) =
+        FlightList::load_with_diagnostics(file, &LoadOptions::default()).unwrap();
+    // the catalogs next to the logbook, then the same definitions in a flight
+    assert_eq!(flight_list.flights.len(), 2);
+    assert_eq!(
+        flight_list.aircrafts.get_model("BCDEFG").unwrap().mtow,
+        1200
+    );
+    assert!(flight_list.airports.same_airport("DLE", "LFGJ"));
+    // another definition of the model and of the airport
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.severity, diagnostic.flight_index))
+        .collect();
+    assert_eq!(
+        errors,
+        [(Severity::Error, Some(2)), (Severity::Error, Some(3))]
+    );
+}
@@ -1,3 +1,4 @@
+use serde::{Serialize, Serializer};
 use std::convert::From;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -8,6 +9,7 @@ use time::Date;
 use time::Duration;
 use time::PrimitiveDateTime;
 use time::Time;
+use time::UtcOffset;
 
 /// This structure is used to express all flight hours.
 #[derive(Copy, Clone, PartialEq)]
@@ -38,6 +40,13 @@ impl Display for FlightTime {
     }
 }
 
+/// Serialized as its number of minutes
+impl Serialize for FlightTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0.whole_minutes())
+    }
+}
+
 //todo fancy
 impl Debug for FlightTime {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -103,11 +112,155 @@ impl Display for FlightDate {
     }
 }
 
+/// Serialized as YYYY-MM-DD
+impl Serialize for FlightDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_date(&self.0, serializer)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TimeOfDate(pub Time);
 
+/// Serialized as HH:MM
+impl Serialize for TimeOfDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serialize a date as YYYY-MM-DD
+pub fn serialize_date<S: Serializer>(date: &Date, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!(
+        "{}-{:02}-{:02}",
+        date.year(),
+        date.month() as u8,
+        date.day()
+    ))
+}
+
+pub fn serialize_optional_date<S: Serializer>(
+    date: &Option<Date>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => serialize_date(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serialize a date and time as YYYY-MM-DD HH:MM, like in the logbook
+pub fn serialize_datetime<S: Serializer>(
+    date: &PrimitiveDateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        date.year(),
+        date.month() as u8,
+        date.day(),
+        date.hour(),
+        date.minute()
+    ))
+}
+
+pub fn serialize_optional_datetime<S: Serializer>(
+    date: &Option<PrimitiveDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => serialize_datetime(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serialize an offset to UTC as +01:00
+pub fn serialize_optional_offset<S: Serializer>(
+    offset: &Option<UtcOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match offset {
+        Some(offset) => serializer.collect_str(&format_args!(
+            "{}{:02}:{:02}",
+            if offset.is_negative() { '-' } else { '+' },
+            offset.whole_hours().abs(),
+            offset.minutes_past_hour().abs()
+        )),
+        None => serializer.serialize_none(),
+    }
+}
+
 impl Display for TimeOfDate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:02}:{:02}", self.0.hour(), self.0.minute())
     }
 }
+
+/// Time base of the dates of a flight :
+/// UTC, or the local time of the departure and arrival airports
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeBase {
+    #[default]
+    Utc,
+    Local,
+}
+
+impl TimeBase {
+    pub fn from_name(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "utc" => Ok(TimeBase::Utc),
+            "local" => Ok(TimeBase::Local),
+            _ => anyhow::bail!("unknown time base {name}, expected utc or local"),
+        }
+    }
+}
+
+#[test]
+fn json_values() {
+    use serde_json::json;
+    use time::macros::{date, datetime, offset, time};
+
+    #[derive(Serialize)]
+    struct Values {
+        flight_time: FlightTime,
+        date: FlightDate,
+        time: TimeOfDate,
+        #[serde(serialize_with = "serialize_datetime")]
+        datetime: PrimitiveDateTime,
+        #[serde(serialize_with = "serialize_optional_datetime")]
+        no_datetime: Option<PrimitiveDateTime>,
+        #[serde(serialize_with = "serialize_optional_date")]
+        optional_date: Option<Date>,
+        #[serde(serialize_with = "serialize_optional_offset")]
+        east: Option<UtcOffset>,
+        #[serde(serialize_with = "serialize_optional_offset")]
+        west: Option<UtcOffset>,
+        #[serde(serialize_with = "serialize_optional_offset")]
+        no_offset: Option<UtcOffset>,
+    }
+    let values = Values {
+        flight_time: FlightTime(Duration::minutes(125)),
+        date: FlightDate(date!(2023 - 03 - 05)),
+        time: TimeOfDate(time!(7:05)),
+        datetime: datetime!(2023-03-26 14:05:30),
+        no_datetime: None,
+        optional_date: Some(date!(2015 - 12 - 31)),
+        east: Some(offset!(+1)),
+        west: Some(offset!(-3:30)),
+        no_offset: None,
+    };
+    assert_eq!(
+        serde_json::to_value(values).unwrap(),
+        json!({
+            "flight_time": 125,
+            "date": "2023-03-05",
+            "time": "07:05",
+            "datetime": "2023-03-26 14:05",
+            "no_datetime": null,
+            "optional_date": "2015-12-31",
+            "east": "+01:00",
+            "west": "-03:30",
+            "no_offset": null,
+        })
+    );
+}
@@ -0,0 +1,79 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+/// Mean radius of the earth in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// A position on the earth, latitude and longitude in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self> {
+        if !(-90.0..=90.0).contains(&latitude) {
+            bail!("latitude {latitude} out of -90..90");
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            bail!("longitude {longitude} out of -180..180");
+        }
+        Ok(Coordinates {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Angle between both positions seen from the center of the earth,
+    /// in radians (haversine formula)
+    fn central_angle(&self, other: &Coordinates) -> f64 {
+        let (lat_a, lat_b) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_lat = lat_b - lat_a;
+        let delta_lon = (other.longitude - self.longitude).to_radians();
+        let h = (delta_lat / 2.0).sin().powi(2)
+            + lat_a.cos() * lat_b.cos() * (delta_lon / 2.0).sin().powi(2);
+        2.0 * h.sqrt().min(1.0).asin()
+    }
+
+    /// Great circle distance in nautical miles
+    pub fn distance_nm(&self, other: &Coordinates) -> f64 {
+        EARTH_RADIUS_NM * self.central_angle(other)
+    }
+
+    /// The position at fraction (0 to 1) of the great circle route to other
+    pub fn interpolate(&self, other: &Coordinates, fraction: f64) -> Coordinates {
+        let angle = self.central_angle(other);
+        if angle < 1e-9 {
+            return *self;
+        }
+        let a = ((1.0 - fraction) * angle).sin() / angle.sin();
+        let b = (fraction * angle).sin() / angle.sin();
+        let (lat_a, lon_a) = (self.latitude.to_radians(), self.longitude.to_radians());
+        let (lat_b, lon_b) = (other.latitude.to_radians(), other.longitude.to_radians());
+        let x = a * lat_a.cos() * lon_a.cos() + b * lat_b.cos() * lon_b.cos();
+        let y = a * lat_a.cos() * lon_a.sin() + b * lat_b.cos() * lon_b.sin();
+        let z = a * lat_a.sin() + b * lat_b.sin();
+        Coordinates {
+            latitude: z.atan2((x * x + y * y).sqrt()).to_degrees(),
+            longitude: y.atan2(x).to_degrees(),
+        }
+    }
+}
+
+#[test]
+fn great_circle_interpolation() {
+    let grenoble = Coordinates::new(45.3629, 5.3294).unwrap();
+    let new_york = Coordinates::new(40.6398, -73.7789).unwrap();
+    assert_eq!(grenoble.interpolate(&new_york, 0.0), grenoble);
+    let arrival = grenoble.interpolate(&new_york, 1.0);
+    assert!((arrival.latitude - new_york.latitude).abs() < 1e-6);
+    assert!((arrival.longitude - new_york.longitude).abs() < 1e-6);
+    // the great circle goes north of both airports
+    assert!(grenoble.interpolate(&new_york, 0.5).latitude > 50.0);
+    assert!(Coordinates::new(91.0, 0.0).is_err());
+    // about 3300 NM
+    let distance = grenoble.distance_nm(&new_york);
+    assert!((3250.0..3350.0).contains(&distance), "distance {distance}");
+    assert_eq!(grenoble.distance_nm(&grenoble), 0.0);
+}
@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use strict_yaml_rust::StrictYaml;
+
+use super::utils::{format_unknown_keys, optional_string, unknown_keys};
+
+/// Keys of a logbook manifest
+const MANIFEST_KEYS: &[&str] = &["include", "aircraft", "airports", "airport_data"];
+
+/// A logbook split across several files.
+/// The top level yaml file is a mapping instead of a vector of flights :
+///
+/// ```yaml
+/// include:
+///   - 2019.yaml
+///   - flights/*.yaml
+///   - older_flights/
+/// aircraft: aircraft.yaml
+/// airports: airports.yaml
+/// airport_data: ourairports.csv
+/// ```
+///
+/// include : files, glob patterns or directories of flight files,
+///   a glob or a directory gives its files in alphabetical order.
+/// aircraft, airports : optional catalogs.
+/// airport_data : optional airport reference data, an OurAirports csv.
+/// The paths are relative to the manifest directory.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub flight_files: Vec<PathBuf>,
+    pub aircraft_catalog_file: Option<PathBuf>,
+    pub airport_catalog_file: Option<PathBuf>,
+    pub airport_data_file: Option<PathBuf>,
+    /// include patterns matching no file
+    pub empty_patterns: Vec<String>,
+    // the include entries, relative to directory
+    directory: PathBuf,
+    include: Vec<String>,
+}
+
+impl Manifest {
+    pub fn from_yaml(yaml: &StrictYaml, manifest_file: &Path) -> Result<Self> {
+        let unknown = unknown_keys(yaml, &[MANIFEST_KEYS]);
+        if !unknown.is_empty() {
+            bail!(
+                "Unknown keys in manifest : {}",
+                format_unknown_keys(&unknown)
+            );
+        }
+        let directory = manifest_file.parent().unwrap_or(Path::new(""));
+        let patterns = match &yaml["include"] {
+            StrictYaml::String(pattern) => vec![pattern.as_str()],
+            StrictYaml::Array(patterns) => patterns
+                .iter()
+                .map(|pattern| {
+                    pattern
+                        .as_str()
+                        .context("[include] must be a list of files")
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => bail!("[include] missing in manifest, expected a list of files"),
+        };
+        let mut flight_files = Vec::new();
+        let mut empty_patterns = Vec::new();
+        for pattern in patterns.iter() {
+            let files = expand_pattern(directory, pattern)?;
+            if files.is_empty() {
+                empty_patterns.push(pattern.to_string());
+            }
+            flight_files.extend(files);
+        }
+        Ok(Manifest {
+            flight_files,
+            aircraft_catalog_file: optional_string(yaml, "aircraft").map(|f| directory.join(f)),
+            airport_catalog_file: optional_string(yaml, "airports").map(|f| directory.join(f)),
+            airport_data_file: optional_string(yaml, "airport_data").map(|f| directory.join(f)),
+            empty_patterns,
+            directory: directory.to_path_buf(),
+            include: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        })
+    }
+
+    /// The flight files of the include entries, listed again :
+    /// with the files added to a directory or to the matches of a glob
+    /// since the manifest was read
+    pub fn list_flight_files(&self) -> Vec<PathBuf> {
+        self.include
+            .iter()
+            .flat_map(|pattern| expand_pattern(&self.directory, pattern).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// The files of an include entry :
+/// a directory gives its yaml files, a glob pattern its matches,
+/// both in alphabetical order.
+fn expand_pattern(directory: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let path = directory.join(pattern);
+    if path.is_dir() {
+        return glob_files(&path.join("*.yaml"));
+    }
+    if pattern.contains(['*', '?', '[']) {
+        return glob_files(&path);
+    }
+    Ok(vec![path])
+}
+
+fn glob_files(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.to_string_lossy();
+    let mut files = glob::glob(&pattern)
+        .with_context(|| format!("bad include pattern {pattern}"))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("cannot read the files of {pattern}"))?;
+    files.retain(|file| file.is_file());
+    files.sort();
+    Ok(files)
+}
+
+#[test]
+fn includes() {
+    use super::diagnostic::Severity;
+    use super::flightlist::FlightList;
+    use super::options::LoadOptions;
+
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests/manifest");
+    let logbook = format!("{directory}/logbook.yaml");
+    let yaml = strict_yaml_rust::StrictYamlLoader::load_from_str(
+        &std::fs::read_to_string(&logbook).unwrap(),
+    )
+    .unwrap()
+    .remove(0);
+    let manifest = Manifest::from_yaml(&yaml, Path::new(&logbook)).unwrap();
+    // a file, the yaml files of a directory, then the matches of a glob,
+    // each in alphabetical order
+    let files: Vec<_> = manifest
+        .flight_files
+        .iter()
+        .map(|file| file.strip_prefix(directory).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        files,
+        [
+            "2019.yaml",
+            "flights/a.yaml",
+            "flights/b.yaml",
+            "more/c.yaml",
+            "more/d.yaml"
+        ]
+    );
+    assert_eq!(manifest.empty_patterns, ["missing/*.yaml"]);
+
+    // the flights in the order of the files,
+    // the errors located in the flight file
+    let (flight_list, diagnostics) =
+        FlightList::load_with_diagnostics(&logbook, &LoadOptions::default()).unwrap();
+    let dates: Vec<_> = flight_list
+        .flights
+        .iter()
+        .map(|flight| flight.date.to_string())
+        .collect();
+    assert_eq!(
+        dates,
+        ["01/01/2019", "01/01/2020", "01/02/2020", "01/01/2021"]
+    );
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| diagnostic.file.as_str())
+        .collect();
+    assert_eq!(errors, [format!("{directory}/more/c.yaml")]);
+}
@@ -1,11 +1,23 @@
 //pub mod aircraft;
 mod aircraftnewversion;
 mod airport;
+mod airportreference;
+mod cache;
+pub mod diagnostic;
 mod extracttime;
 pub mod flight;
 mod flightlist;
 pub mod flighttime;
-mod utils;
+mod geo;
+mod manifest;
+mod night;
+mod options;
+pub mod previousexperience;
+pub mod sourcemap;
+pub(crate) mod utils;
 mod validator;
 pub use aircraftnewversion::AircraftModel;
+pub use cache::{FlightListCache, LoadFailure};
 pub use flightlist::FlightList;
+pub use options::LoadOptions;
+pub use validator::ValidationRules;
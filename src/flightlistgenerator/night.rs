@@ -0,0 +1,80 @@
+use time::{Duration, PrimitiveDateTime};
+
+use super::geo::Coordinates;
+
+/// EASA night : from the end of evening civil twilight to the
+/// beginning of morning civil twilight, the sun 6° below the horizon
+const CIVIL_TWILIGHT_ELEVATION: f64 = -6.0;
+
+/// Night time of a flight between two airports, in UTC.
+/// The position of the aircraft is interpolated along the great circle
+/// route, the sun is checked at the middle of each minute.
+pub fn night_time(
+    departure: &Coordinates,
+    arrival: &Coordinates,
+    date_start: PrimitiveDateTime,
+    date_end: PrimitiveDateTime,
+) -> Duration {
+    let total_minutes = (date_end - date_start).whole_minutes();
+    let mut night_minutes = 0;
+    for minute in 0..total_minutes {
+        let fraction = (minute as f64 + 0.5) / total_minutes as f64;
+        let position = departure.interpolate(arrival, fraction);
+        let date = date_start + Duration::minutes(minute) + Duration::seconds(30);
+        if sun_elevation(&position, date) < CIVIL_TWILIGHT_ELEVATION {
+            night_minutes += 1;
+        }
+    }
+    Duration::minutes(night_minutes)
+}
+
+/// Elevation of the sun above the horizon in degrees,
+/// low precision formulas of the astronomical almanac
+/// (about 0.01° between 1950 and 2050)
+pub fn sun_elevation(position: &Coordinates, utc: PrimitiveDateTime) -> f64 {
+    let unix_time = utc.assume_utc().unix_timestamp() as f64;
+    // days since J2000.0
+    let days = unix_time / 86400.0 + 2440587.5 - 2451545.0;
+
+    let mean_longitude = (280.460 + 0.9856474 * days).rem_euclid(360.0);
+    let mean_anomaly = (357.528 + 0.9856003 * days).rem_euclid(360.0).to_radians();
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin())
+            .to_radians();
+    let obliquity = (23.439 - 0.0000004 * days).to_radians();
+
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+    let right_ascension =
+        (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+
+    // sidereal time at Greenwich, in degrees
+    let sidereal_time = (280.46061837 + 360.98564736629 * days).rem_euclid(360.0);
+    let hour_angle = (sidereal_time + position.longitude).to_radians() - right_ascension;
+
+    let latitude = position.latitude.to_radians();
+    (latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees()
+}
+
+#[test]
+fn civil_twilight_night() {
+    let date = |input| super::extracttime::extract_date(input).unwrap();
+    let paris = Coordinates::new(49.0097, 2.5479).unwrap();
+    let grenoble = Coordinates::new(45.3629, 5.3294).unwrap();
+    // June solstice, the sun culminates at about 64.5° over Paris
+    let noon = sun_elevation(&paris, date("2023-06-21 11:50"));
+    assert!((noon - 64.5).abs() < 0.5, "elevation {noon}");
+    // winter, flights around midnight are all night and around noon all day
+    let (start, end) = (date("2023-12-21 23:00"), date("2023-12-22 00:10"));
+    assert_eq!(
+        night_time(&paris, &grenoble, start, end),
+        Duration::minutes(70)
+    );
+    let (start, end) = (date("2023-12-21 11:00"), date("2023-12-21 12:10"));
+    assert_eq!(night_time(&paris, &grenoble, start, end), Duration::ZERO);
+    // take-off in the evening dusk : civil twilight ends about 16:35 UTC in Paris
+    let (start, end) = (date("2023-12-21 16:00"), date("2023-12-21 17:00"));
+    let night = night_time(&paris, &paris, start, end).whole_minutes();
+    assert!((20..=30).contains(&night), "night {night}");
+}
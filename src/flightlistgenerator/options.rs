@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use super::validator::ValidationRules;
+
+/// Options of the logbook loader, given on the command line
+/// rules : severity of the validation rules
+/// aircraft_catalog_file : the aircraft catalog, by default the one
+///   of the manifest, or aircraft.yaml next to the logbook
+/// airport_catalog_file : the airport catalog, by default the one
+///   of the manifest, or airports.yaml next to the logbook
+/// airport_data_file : the airport reference data, an OurAirports csv,
+///   by default the one of the manifest, or ourairports.csv next to the logbook
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    pub rules: ValidationRules,
+    pub aircraft_catalog_file: Option<PathBuf>,
+    pub airport_catalog_file: Option<PathBuf>,
+    pub airport_data_file: Option<PathBuf>,
+}
+
+impl LoadOptions {
+    /// The aircraft catalog to load with the logbook, if any
+    pub fn aircraft_catalog(&self, logbook: &Path, manifest: Option<&Path>) -> Option<PathBuf> {
+        catalog(
+            &self.aircraft_catalog_file,
+            manifest,
+            logbook,
+            "aircraft.yaml",
+        )
+    }
+
+    /// The airport catalog to load with the logbook, if any
+    pub fn airport_catalog(&self, logbook: &Path, manifest: Option<&Path>) -> Option<PathBuf> {
+        catalog(
+            &self.airport_catalog_file,
+            manifest,
+            logbook,
+            "airports.yaml",
+        )
+    }
+
+    /// The airport reference data to load with the logbook, if any
+    pub fn airport_data(&self, logbook: &Path, manifest: Option<&Path>) -> Option<PathBuf> {
+        catalog(
+            &self.airport_data_file,
+            manifest,
+            logbook,
+            "ourairports.csv",
+        )
+    }
+}
+
+/// The catalog given in the options, or in the manifest,
+/// or the default file in the directory of the logbook if it exists
+fn catalog(
+    file: &Option<PathBuf>,
+    manifest: Option<&Path>,
+    logbook: &Path,
+    default_name: &str,
+) -> Option<PathBuf> {
+    match (file, manifest) {
+        (Some(file), _) => Some(file.clone()),
+        (None, Some(file)) => Some(file.to_path_buf()),
+        (None, None) => {
+            let default_file = logbook.with_file_name(default_name);
+            if default_file.is_file() && default_file != logbook {
+                Some(default_file)
+            } else {
+                None
+            }
+        }
+    }
+}
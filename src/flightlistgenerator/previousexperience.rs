@@ -0,0 +1,190 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use strict_yaml_rust::StrictYaml;
+use time::Date;
+
+use super::diagnostic::FieldError;
+use super::extracttime::extract_day;
+use super::flighttime::{serialize_optional_date, FlightTime};
+use super::sourcemap::SourceLocation;
+use super::utils::{optional_bool, optional_duration, optional_string, optional_u32};
+
+/// Keys of a previous experience entry
+pub const PREVIOUS_EXPERIENCE_KEYS: &[&str] = &[
+    "previous_experience",
+    "date",
+    "duration_total",
+    "duration_pic",
+    "copilot_time",
+    "dual_time",
+    "instructor_time",
+    "oc_time_night",
+    "oc_time_ifr",
+    "multi_pilot_time",
+    "single_pilot_time_se",
+    "single_pilot_time_me",
+    "takeoff_day",
+    "takeoff_night",
+    "landing_day",
+    "landing_night",
+    "sim_total_time",
+    "comment",
+];
+
+/// Totals brought forward from a previous logbook, usually on paper.
+/// The entry is in the flight list, with previous_experience: true :
+///
+/// ```yaml
+/// - previous_experience: true
+///   date: 2015-12-31
+///   duration_total: 1250:30
+///   duration_pic: 820:00
+///   landing_day: 1540
+/// ```
+///
+/// date : the day of the last flight of the previous logbook.
+/// The other keys are the totals of the columns, all optional.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreviousExperience {
+    #[serde(serialize_with = "serialize_optional_date")]
+    pub date: Option<Date>,
+    pub total_flight_time: FlightTime,
+    pub pilot_in_command_time: FlightTime,
+    pub copilot_time: FlightTime,
+    pub dual_time: FlightTime,
+    pub instructor_time: FlightTime,
+    pub operational_condition_time_night: FlightTime,
+    pub operational_condition_time_ifr: FlightTime,
+    pub multi_pilot_time: FlightTime,
+    pub single_pilot_time_se: FlightTime,
+    pub single_pilot_time_me: FlightTime,
+    pub takeoff_day: u32,
+    pub takeoff_night: u32,
+    pub landing_day: u32,
+    pub landing_night: u32,
+    pub sim_total_time_of_session: FlightTime,
+    pub remark: String,
+    // Position in the yaml file, set by the flight list
+    pub source: SourceLocation,
+}
+
+impl PreviousExperience {
+    /// true if the yaml element is a previous experience entry
+    pub fn is_previous_experience(yaml: &StrictYaml) -> bool {
+        matches!(optional_bool(yaml, "previous_experience"), Ok(Some(true)))
+    }
+
+    pub fn from_yaml(yaml: &StrictYaml) -> Result<Self> {
+        let date =
+            match optional_string(yaml, "date") {
+                Some(date) => Some(extract_day(date).with_context(|| {
+                    FieldError::new("date", format!("In field [date] : {date}"))
+                })?),
+                None => None,
+            };
+        let time = |key: &str| -> Result<FlightTime> {
+            Ok(FlightTime(
+                optional_duration(yaml, key)?.unwrap_or_default(),
+            ))
+        };
+        let count = |key: &str| -> Result<u32> { Ok(optional_u32(yaml, key)?.unwrap_or(0)) };
+        let previous_experience = PreviousExperience {
+            date,
+            total_flight_time: time("duration_total")?,
+            pilot_in_command_time: time("duration_pic")?,
+            copilot_time: time("copilot_time")?,
+            dual_time: time("dual_time")?,
+            instructor_time: time("instructor_time")?,
+            operational_condition_time_night: time("oc_time_night")?,
+            operational_condition_time_ifr: time("oc_time_ifr")?,
+            multi_pilot_time: time("multi_pilot_time")?,
+            single_pilot_time_se: time("single_pilot_time_se")?,
+            single_pilot_time_me: time("single_pilot_time_me")?,
+            takeoff_day: count("takeoff_day")?,
+            takeoff_night: count("takeoff_night")?,
+            landing_day: count("landing_day")?,
+            landing_night: count("landing_night")?,
+            sim_total_time_of_session: time("sim_total_time")?,
+            remark: optional_string(yaml, "comment").unwrap_or("").to_string(),
+            source: SourceLocation::default(),
+        };
+        previous_experience.validate()?;
+        Ok(previous_experience)
+    }
+
+    /// Add the totals of other, the date is the latest one
+    pub fn add(&mut self, other: &PreviousExperience) {
+        self.date = self.date.max(other.date);
+        self.total_flight_time += other.total_flight_time;
+        self.pilot_in_command_time += other.pilot_in_command_time;
+        self.copilot_time += other.copilot_time;
+        self.dual_time += other.dual_time;
+        self.instructor_time += other.instructor_time;
+        self.operational_condition_time_night += other.operational_condition_time_night;
+        self.operational_condition_time_ifr += other.operational_condition_time_ifr;
+        self.multi_pilot_time += other.multi_pilot_time;
+        self.single_pilot_time_se += other.single_pilot_time_se;
+        self.single_pilot_time_me += other.single_pilot_time_me;
+        self.takeoff_day += other.takeoff_day;
+        self.takeoff_night += other.takeoff_night;
+        self.landing_day += other.landing_day;
+        self.landing_night += other.landing_night;
+        self.sim_total_time_of_session += other.sim_total_time_of_session;
+    }
+
+    /// The partial totals cannot exceed the total time
+    fn validate(&self) -> Result<()> {
+        let total = self.total_flight_time;
+        for (key, time) in [
+            ("duration_pic", self.pilot_in_command_time),
+            ("copilot_time", self.copilot_time),
+            ("dual_time", self.dual_time),
+            ("instructor_time", self.instructor_time),
+            ("oc_time_night", self.operational_condition_time_night),
+            ("oc_time_ifr", self.operational_condition_time_ifr),
+            ("multi_pilot_time", self.multi_pilot_time),
+            ("single_pilot_time_se", self.single_pilot_time_se),
+            ("single_pilot_time_me", self.single_pilot_time_me),
+        ] {
+            if *time > *total {
+                bail!(FieldError::new(
+                    key,
+                    format!("[{key}] {time} greater than the total time {total}")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn previous_experience_totals() {
+    let yaml = |input: &str| {
+        strict_yaml_rust::StrictYamlLoader::load_from_str(input)
+            .unwrap()
+            .remove(0)
+    };
+    let entry = yaml(
+        "previous_experience: true\ndate: 2015-12-31\nduration_total: 1250:30\nduration_pic: 820:00\nlanding_day: 1540",
+    );
+    assert!(PreviousExperience::is_previous_experience(&entry));
+    let mut total = PreviousExperience::from_yaml(&entry).unwrap();
+    assert_eq!(total.total_flight_time.whole_minutes(), 1250 * 60 + 30);
+    assert_eq!(total.landing_day, 1540);
+    let second = PreviousExperience::from_yaml(&yaml(
+        "previous_experience: true\ndate: 2017-06-30\nduration_total: 10:00",
+    ))
+    .unwrap();
+    total.add(&second);
+    assert_eq!(total.total_flight_time.whole_minutes(), 1260 * 60 + 30);
+    assert_eq!(total.date, second.date);
+    // a partial total greater than the total
+    let error = PreviousExperience::from_yaml(&yaml(
+        "previous_experience: true\nduration_total: 10:00\noc_time_night: 12:00",
+    ))
+    .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<FieldError>().unwrap().key,
+        "oc_time_night"
+    );
+}
@@ -0,0 +1,134 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use strict_yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use strict_yaml_rust::scanner::{Marker, ScanError};
+
+/// A position in the yaml file.
+/// line and column both start from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<&Marker> for Position {
+    fn from(marker: &Marker) -> Self {
+        Position {
+            line: marker.line(),
+            column: marker.col() + 1,
+        }
+    }
+}
+
+/// Where a flight comes from
+/// index : index of the flight in the yaml vector
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub index: usize,
+    pub position: Option<Position>,
+}
+
+/// Positions of the elements of the top level yaml vector
+/// and of the keys of each element.
+/// Built from the parser events, since StrictYaml
+/// does not keep the positions.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    elements: Vec<ElementPositions>,
+}
+
+#[derive(Debug)]
+struct ElementPositions {
+    start: Position,
+    keys: HashMap<String, Position>,
+}
+
+impl SourceMap {
+    /// Parse the yaml content.
+    /// A content which does not parse gives an empty map.
+    pub fn from_yaml_str(content: &str) -> Self {
+        let mut builder = SourceMapBuilder::default();
+        let mut parser = Parser::new(content.chars());
+        if parser.load(&mut builder, false).is_err() {
+            return SourceMap::default();
+        }
+        builder.source_map
+    }
+
+    /// Position of a key of the element number index,
+    /// or the position of the element itself if the key is unknown
+    pub fn position(&self, index: usize, key: Option<&str>) -> Option<Position> {
+        let element = self.elements.get(index)?;
+        key.and_then(|key| element.keys.get(key))
+            .or(Some(&element.start))
+            .copied()
+    }
+}
+
+/// Receive the parser events and keep track of the depth.
+/// depth 1 : the top level vector
+/// depth 2 : an element (a flight) of the vector
+#[derive(Default)]
+struct SourceMapBuilder {
+    source_map: SourceMap,
+    depth: usize,
+    expect_key: bool,
+}
+
+impl MarkedEventReceiver for SourceMapBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) -> Result<(), ScanError> {
+        match event {
+            Event::SequenceStart(_) | Event::MappingStart(_) => {
+                if self.depth == 2 {
+                    // a value of an element key
+                    self.expect_key = true;
+                }
+                self.depth += 1;
+                if self.depth == 2 {
+                    self.source_map.elements.push(ElementPositions {
+                        start: Position::from(&mark),
+                        keys: HashMap::new(),
+                    });
+                    self.expect_key = true;
+                }
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.depth -= 1;
+            }
+            Event::Scalar(value, _, _) if self.depth == 2 => {
+                if self.expect_key {
+                    if let Some(element) = self.source_map.elements.last_mut() {
+                        // the mapping start marker is after the first key
+                        if element.keys.is_empty() {
+                            element.start = Position::from(&mark);
+                        }
+                        element.keys.insert(value, Position::from(&mark));
+                    }
+                }
+                self.expect_key = !self.expect_key;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn source_map_positions() {
+    let content = "- date_start: \"2001-01-01 05:00\"\n  pic: JOHN DOE\n\n- date_start: \"2001-01-02 05:00\"\n  comment: hello\n";
+    let source_map = SourceMap::from_yaml_str(content);
+    assert_eq!(
+        source_map.position(0, Some("pic")),
+        Some(Position { line: 2, column: 3 })
+    );
+    assert_eq!(
+        source_map.position(1, Some("comment")),
+        Some(Position { line: 5, column: 3 })
+    );
+    assert_eq!(
+        source_map.position(1, Some("missing")),
+        Some(Position { line: 4, column: 3 })
+    );
+    assert_eq!(source_map.position(2, None), None);
+}
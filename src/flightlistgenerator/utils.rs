@@ -4,6 +4,7 @@ use anyhow::Result;
 use strict_yaml_rust::StrictYaml;
 use time::{Duration, PrimitiveDateTime};
 
+use super::diagnostic::FieldError;
 use super::extracttime::extract_date;
 use super::extracttime::extract_duration;
 
@@ -18,10 +19,22 @@ fn mandatory_key<'a>(yaml: &'a StrictYaml, key: &str) -> Result<&'a str> {
 pub fn mandatory_datetime(yaml: &StrictYaml, key: &str) -> Result<PrimitiveDateTime> {
     let raw_datetime = mandatory_key(yaml, key)?;
     let datetime = extract_date(raw_datetime)
-        .with_context(|| format!("In field [{}] : {}", key, raw_datetime))?;
+        .with_context(|| FieldError::new(key, format!("In field [{}] : {}", key, raw_datetime)))?;
     Ok(datetime)
 }
 
+pub fn optional_datetime(yaml: &StrictYaml, key: &str) -> Result<Option<PrimitiveDateTime>> {
+    match optional_string(yaml, key) {
+        Some(raw_datetime) => {
+            let datetime = extract_date(raw_datetime).with_context(|| {
+                FieldError::new(key, format!("In field [{}] : {}", key, raw_datetime))
+            })?;
+            Ok(Some(datetime))
+        }
+        None => Ok(None),
+    }
+}
+
 pub fn optional_string<'a>(yaml: &'a StrictYaml, key: &str) -> Option<&'a str> {
     match &yaml[key] {
         StrictYaml::String(s) => Some(s),
@@ -43,8 +56,8 @@ pub fn optional_duration(yaml: &StrictYaml, key: &str) -> Result<Option<Duration
     //
     match optional_string(yaml, key) {
         Some(raw_duration) => {
-            let duration =
-                extract_duration(&raw_duration).with_context(|| format!("in field : [{}]", key))?;
+            let duration = extract_duration(raw_duration)
+                .with_context(|| FieldError::new(key, format!("in field : [{}]", key)))?;
             Ok(Some(duration))
         }
         None => Ok(None),
@@ -56,7 +69,19 @@ pub fn optional_u8(yaml: &StrictYaml, key: &str) -> Result<Option<u8>> {
         Some(raw_number) => {
             let number = raw_number
                 .parse::<u8>()
-                .with_context(|| format!("in field : [{}]", key))?;
+                .with_context(|| FieldError::new(key, format!("in field : [{}]", key)))?;
+            Ok(Some(number))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn optional_u32(yaml: &StrictYaml, key: &str) -> Result<Option<u32>> {
+    match optional_string(yaml, key) {
+        Some(raw_number) => {
+            let number = raw_number
+                .parse::<u32>()
+                .with_context(|| FieldError::new(key, format!("in field : [{}]", key)))?;
             Ok(Some(number))
         }
         None => Ok(None),
@@ -68,13 +93,90 @@ pub fn optional_bool(yaml: &StrictYaml, key: &str) -> Result<Option<bool>> {
         Some(raw_boolean) => {
             let boolean = raw_boolean
                 .parse::<bool>()
-                .with_context(|| format!("in field : [{}]", key))?;
+                .with_context(|| FieldError::new(key, format!("in field : [{}]", key)))?;
             Ok(Some(boolean))
         }
         None => Ok(None),
     }
 }
 
+/// List the keys of a yaml element which are not in the known key sets.
+/// Each unknown key comes with the closest known key, if any is close enough.
+pub fn unknown_keys<'a>(
+    yaml: &'a StrictYaml,
+    known_keys: &[&[&'static str]],
+) -> Vec<(&'a str, Option<&'static str>)> {
+    let mut unknown = Vec::new();
+    if let StrictYaml::Hash(hash) = yaml {
+        for key in hash.keys().filter_map(|key| key.as_str()) {
+            if !known_keys.iter().any(|keys| keys.contains(&key)) {
+                unknown.push((key, closest_key(key, known_keys)));
+            }
+        }
+    }
+    unknown
+}
+
+/// Format the result of unknown_keys on one line
+pub fn format_unknown_keys(unknown: &[(&str, Option<&str>)]) -> String {
+    unknown
+        .iter()
+        .map(|(key, suggestion)| format_unknown_key(key, *suggestion))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn format_unknown_key(key: &str, suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!("unknown key [{key}], did you mean [{suggestion}] ?"),
+        None => format!("unknown key [{key}]"),
+    }
+}
+
+/// The known key with the smallest edit distance,
+/// only if the distance is small compared to the key length
+fn closest_key(key: &str, known_keys: &[&[&'static str]]) -> Option<&'static str> {
+    let max_distance = (key.chars().count() / 3).clamp(1, 3);
+    known_keys
+        .iter()
+        .flat_map(|keys| keys.iter())
+        .map(|known_key| (levenshtein(key, known_key), *known_key))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known_key)| known_key)
+}
+
+/// Edit distance between two strings
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, char_a) in a.chars().enumerate() {
+        let mut current_row = vec![i + 1];
+        for (j, char_b) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(char_a != *char_b);
+            let insertion = current_row[j] + 1;
+            let deletion = previous_row[j + 1] + 1;
+            current_row.push(substitution.min(insertion).min(deletion));
+        }
+        previous_row = current_row;
+    }
+    previous_row[b.len()]
+}
+
+#[test]
+fn unknown_keys_suggestions() {
+    use strict_yaml_rust::StrictYamlLoader;
+    let yaml = &StrictYamlLoader::load_from_str(
+        "oc_time_nigth: \"01:00\"\nduration_total: \"01:00\"\nfoo: bar",
+    )
+    .unwrap()[0];
+    let unknown = unknown_keys(yaml, &[&["oc_time_night", "duration_total"]]);
+    assert_eq!(
+        unknown,
+        vec![("oc_time_nigth", Some("oc_time_night")), ("foo", None)]
+    );
+}
+
 // pub fn optional_f32(yaml: &StrictYaml, key: &str) -> Result<Option<f32>> {
 //     match optional_string(yaml, key) {
 //         Some(raw_number) => {
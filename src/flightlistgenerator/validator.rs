@@ -1,7 +1,16 @@
+use std::collections::HashMap;
+
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
+use indexmap::IndexMap;
 use time::{Duration, PrimitiveDateTime};
 
+use super::diagnostic::{Diagnostic, FieldError, Severity};
+use super::flight::Flight;
+use super::flighttime::FlightTime;
+use super::FlightList;
+
 pub fn validate_duration(
     duration: Duration,
     date_start: &PrimitiveDateTime,
@@ -38,11 +47,450 @@ pub fn validate_date(date_start: &PrimitiveDateTime, date_end: &PrimitiveDateTim
     Ok(())
 }
 
-/// todo for flightlist
-/// Add checks :
-/// flight time check, chronology,..
-pub fn perform_basic_check() {
+/// off-block <= take-off < landing <= on-block
+/// The airborne time is then within the block time.
+/// No rule compares it to duration_total, which is already the block time.
+pub fn validate_airborne(
+    off_block: &PrimitiveDateTime,
+    takeoff: &PrimitiveDateTime,
+    landing: &PrimitiveDateTime,
+    on_block: &PrimitiveDateTime,
+) -> Result<()> {
+    if takeoff < off_block {
+        bail!(FieldError::new(
+            "date_takeoff",
+            format!("take-off {takeoff} before off-block date_start={off_block}")
+        ));
+    }
+    if landing <= takeoff {
+        bail!(FieldError::new(
+            "date_landing",
+            format!("landing {landing} not after take-off {takeoff}")
+        ));
+    }
+    if landing > on_block {
+        bail!(FieldError::new(
+            "date_landing",
+            format!("landing {landing} after on-block date_end={on_block}")
+        ));
+    }
+    Ok(())
+}
+
+/// Checks across the whole flight list.
+/// All the findings are warnings, with the index of both flights :
+/// - flights out of chronological order
+/// - overlapping flights or simulator sessions.
+///   The logbook belongs to one pilot, two flights cannot overlap.
+/// - an aircraft departing from another airport than its last arrival
+pub fn perform_basic_check(flight_list: &FlightList) -> Vec<Diagnostic> {
+    let flights = &flight_list.flights;
+    let mut diagnostics = Vec::new();
+
     // chronological order
-    // check airports
-    // check
+    for (previous, flight) in flights.iter().zip(flights.iter().skip(1)) {
+        if flight.date_start < previous.date_start {
+            diagnostics.push(warning(
+                flight,
+                format!(
+                    "flight number {} starts before the previous flight {} ({} {})",
+                    flight.source.index,
+                    other_entry(flight, previous),
+                    previous.date,
+                    previous.time_departure
+                ),
+            ));
+        }
+    }
+
+    // overlaps : sort by start date,
+    // and compare each flight to the one ending last before it
+    let mut sorted_flights: Vec<&Flight> = flights.iter().collect();
+    sorted_flights.sort_by_key(|flight| flight.date_start);
+    let mut last_ending: Option<&Flight> = None;
+    for flight in sorted_flights.iter().copied() {
+        if let Some(last_ending) = last_ending {
+            if flight.date_start < last_ending.date_end {
+                diagnostics.push(warning(
+                    flight,
+                    format!(
+                        "{} number {} overlaps {} {} ({} {} - {})",
+                        entry_kind(flight),
+                        flight.source.index,
+                        entry_kind(last_ending),
+                        other_entry(flight, last_ending),
+                        last_ending.date,
+                        last_ending.time_departure,
+                        last_ending.time_arrival
+                    ),
+                ));
+            }
+        }
+        if last_ending.is_none_or(|last_ending| flight.date_end > last_ending.date_end) {
+            last_ending = Some(flight);
+        }
+    }
+
+    // continuity : an aircraft departs from its last arrival airport,
+    // in date order so that a flight out of order does not break it
+    let mut last_arrivals: HashMap<&str, &Flight> = HashMap::new();
+    for flight in sorted_flights.iter().filter(|flight| !flight.is_sim) {
+        if let Some(previous) = last_arrivals.get(flight.immatriculation.as_str()) {
+            if !flight_list
+                .airports
+                .same_airport(&previous.airport_arrival, &flight.airport_departure)
+            {
+                diagnostics.push(warning(
+                    flight,
+                    format!(
+                        "{} departs from {} but landed at {} on flight {}",
+                        flight.immatriculation,
+                        flight.airport_departure,
+                        previous.airport_arrival,
+                        other_entry(flight, previous)
+                    ),
+                ));
+            }
+        }
+        last_arrivals.insert(&flight.immatriculation, flight);
+    }
+    diagnostics
+}
+
+fn entry_kind(flight: &Flight) -> &'static str {
+    if flight.is_sim {
+        "simulator session"
+    } else {
+        "flight"
+    }
+}
+
+/// Number of the other flight, with its file
+/// when the logbook is split across several files
+fn other_entry(flight: &Flight, other: &Flight) -> String {
+    if flight.source.file == other.source.file {
+        format!("number {}", other.source.index)
+    } else {
+        format!("number {} of {}", other.source.index, other.source.file)
+    }
+}
+
+fn warning(flight: &Flight, message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        message,
+        file: flight.source.file.clone(),
+        position: flight.source.position,
+        flight_index: Some(flight.source.index),
+        date_start: Some(format!("{} {}", flight.date, flight.time_departure)),
+        snippet: None,
+    }
+}
+
+/// Rules checking the time columns of one flight.
+/// Name, default severity, description
+const FLIGHT_RULES: &[(&str, Option<Severity>, &str)] = &[
+    (
+        "ifr_exceeds_total",
+        Some(Severity::Error),
+        "[oc_time_ifr] greater than the total time",
+    ),
+    (
+        "night_exceeds_total",
+        Some(Severity::Error),
+        "[oc_time_night] greater than the total time",
+    ),
+    (
+        "dual_exceeds_total",
+        Some(Severity::Error),
+        "[dual_time] greater than the total time",
+    ),
+    (
+        "instructor_exceeds_total",
+        Some(Severity::Error),
+        "[instructor_time] greater than the total time",
+    ),
+    (
+        "function_times_exceed_total",
+        Some(Severity::Warning),
+        "pic + copilot + dual time greater than the total time",
+    ),
+    (
+        "function_times_missing",
+        Some(Severity::Warning),
+        "no pic, copilot or dual time logged",
+    ),
+    (
+        "night_landings_without_night_time",
+        Some(Severity::Warning),
+        "night take-offs or landings logged with no night time",
+    ),
+    (
+        "night_time_differs",
+        Some(Severity::Warning),
+        "[oc_time_night] differs from the night time computed from civil twilight",
+    ),
+];
+
+/// Difference allowed between the logged and the computed night time
+const NIGHT_TIME_TOLERANCE: Duration = Duration::minutes(5);
+
+/// Severity of each flight rule, None when the rule is off
+#[derive(Debug, Clone)]
+pub struct ValidationRules {
+    levels: IndexMap<&'static str, Option<Severity>>,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        ValidationRules {
+            levels: FLIGHT_RULES
+                .iter()
+                .map(|(name, severity, _)| (*name, *severity))
+                .collect(),
+        }
+    }
+}
+
+impl ValidationRules {
+    /// Change the severity of a rule from a string rule=level,
+    /// level is error, warning or off
+    pub fn set_from_str(&mut self, rule_and_level: &str) -> Result<()> {
+        let (rule, level) = rule_and_level
+            .split_once('=')
+            .with_context(|| format!("expected rule=level, found {rule_and_level}"))?;
+        let severity = match level {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "off" => None,
+            _ => bail!("unknown level {level} for rule {rule}, expected error, warning or off"),
+        };
+        let current = self.levels.get_mut(rule).with_context(|| {
+            format!(
+                "unknown rule {rule}, the rules are : {}",
+                FLIGHT_RULES
+                    .iter()
+                    .map(|(name, _, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+        *current = severity;
+        Ok(())
+    }
+
+    fn severity(&self, rule: &str) -> Option<Severity> {
+        self.levels.get(rule).copied().flatten()
+    }
+}
+
+/// A broken rule on one flight
+/// key : the yaml key to locate the finding in the file
+pub struct Finding {
+    pub severity: Severity,
+    pub key: &'static str,
+    pub message: String,
+}
+
+/// Check the time columns of a flight against the rules.
+/// Simulator sessions are not checked.
+pub fn check_flight_times(flight: &Flight, rules: &ValidationRules) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if flight.is_sim {
+        return findings;
+    }
+    let total = flight.total_flight_time;
+    let mut check = |rule: &str, key: &'static str, broken: bool, values: String| {
+        if let (true, Some(severity)) = (broken, rules.severity(rule)) {
+            let (_, _, description) = FLIGHT_RULES
+                .iter()
+                .find(|(name, _, _)| *name == rule)
+                .unwrap();
+            findings.push(Finding {
+                severity,
+                key,
+                message: format!("{description} : {values} (rule {rule})"),
+            });
+        }
+    };
+    for (rule, key, time) in [
+        (
+            "ifr_exceeds_total",
+            "oc_time_ifr",
+            flight.operational_condition_time_ifr,
+        ),
+        (
+            "night_exceeds_total",
+            "oc_time_night",
+            flight.operational_condition_time_night,
+        ),
+        ("dual_exceeds_total", "dual_time", flight.dual_time),
+        (
+            "instructor_exceeds_total",
+            "instructor_time",
+            flight.instructor_time,
+        ),
+    ] {
+        check(rule, key, *time > *total, format!("{time} > total {total}"));
+    }
+
+    // the instructor time is not added, it is also logged as pic time
+    let mut function_times = flight.pilot_in_command_time;
+    function_times += flight.copilot_time;
+    function_times += flight.dual_time;
+    check(
+        "function_times_exceed_total",
+        "duration_pic",
+        *function_times > *total,
+        format!("{function_times} > total {total}"),
+    );
+    check(
+        "function_times_missing",
+        "date_start",
+        function_times == FlightTime::ZERO,
+        format!("total {total}"),
+    );
+    check(
+        "night_landings_without_night_time",
+        "oc_time_night",
+        flight.takeoff_night + flight.landing_night > 0
+            && flight.operational_condition_time_night == FlightTime::ZERO,
+        format!(
+            "{} take-off(s), {} landing(s)",
+            flight.takeoff_night, flight.landing_night
+        ),
+    );
+    if let Some(computed) = flight.computed_night_time {
+        let logged = flight.operational_condition_time_night;
+        check(
+            "night_time_differs",
+            "oc_time_night",
+            (*logged - *computed).abs() > NIGHT_TIME_TOLERANCE,
+            format!("{logged} logged, {computed} computed"),
+        );
+    }
+    findings
+}
+
+#[test]
+fn flight_list_checks() {
+    use super::options::LoadOptions;
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests/checks.yaml");
+    let (flight_list, _) =
+        FlightList::load_with_diagnostics(file, &LoadOptions::default()).unwrap();
+    let warnings: Vec<_> = perform_basic_check(&flight_list)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.flight_index.unwrap(), diagnostic.message))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            (
+                1,
+                "flight number 1 starts before the previous flight number 0 (02/01/2001 05:00)"
+                    .to_string()
+            ),
+            (
+                3,
+                "flight number 3 overlaps flight number 2 (03/01/2001 05:00 - 06:00)".to_string()
+            ),
+            (
+                4,
+                "simulator session number 4 overlaps flight number 3 (03/01/2001 05:30 - 06:30)"
+                    .to_string()
+            ),
+            (
+                5,
+                "ABCDEF departs from LYS but landed at GNB on flight number 2".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn flight_time_rules() {
+    use super::aircraftnewversion::AircraftDataBase;
+    use super::airport::AirportList;
+    use strict_yaml_rust::StrictYamlLoader;
+
+    let flight = |times: &str| {
+        let yaml = format!(
+            "date_start: \"2001-01-01 05:00\"
+date_end: \"2001-01-01 06:00\"
+duration_total: \"01:00\"
+apt_departure_iata: GNB
+apt_departure_icao: LFLS
+apt_departure_name: Grenoble
+apt_arrival_iata: DLE
+apt_arrival_icao: LFGJ
+apt_arrival_name: Dole
+immatriculation: ABCDEF
+acmodel: C172
+actype: SEP
+nb_engines: 1
+mtow: 1200
+multipilot: false
+cat1: light
+cat2: none
+cat3: none
+pic: SELF
+{times}"
+        );
+        let yaml = &StrictYamlLoader::load_from_str(&yaml).unwrap()[0];
+        Flight::from_yaml(
+            yaml,
+            &mut AircraftDataBase::empty(),
+            &mut AirportList::new(),
+        )
+        .unwrap()
+    };
+    let findings = |times: &str, rules: &ValidationRules| {
+        check_flight_times(&flight(times), rules)
+            .into_iter()
+            .map(|finding| (finding.severity, finding.key))
+            .collect::<Vec<_>>()
+    };
+    let mut rules = ValidationRules::default();
+    assert_eq!(findings("duration_pic: \"01:00\"", &rules), []);
+    assert_eq!(
+        findings(
+            "duration_pic: \"01:00\"\noc_time_ifr: \"01:30\"\ninstructor_time: \"01:30\"",
+            &rules
+        ),
+        [
+            (Severity::Error, "oc_time_ifr"),
+            (Severity::Error, "instructor_time")
+        ]
+    );
+    assert_eq!(
+        findings("duration_pic: \"01:00\"\ndual_time: \"00:30\"", &rules),
+        [(Severity::Warning, "duration_pic")]
+    );
+    // an instructor logs the flight as pic
+    assert_eq!(
+        findings(
+            "duration_pic: \"01:00\"\ninstructor_time: \"01:00\"",
+            &rules
+        ),
+        []
+    );
+    assert_eq!(
+        findings("landing_night: 1", &rules),
+        [
+            (Severity::Warning, "date_start"),
+            (Severity::Warning, "oc_time_night")
+        ]
+    );
+
+    rules.set_from_str("function_times_missing=off").unwrap();
+    rules
+        .set_from_str("night_landings_without_night_time=error")
+        .unwrap();
+    assert_eq!(
+        findings("landing_night: 1", &rules),
+        [(Severity::Error, "oc_time_night")]
+    );
+    assert!(rules.set_from_str("function_times_missing").is_err());
+    assert!(rules.set_from_str("unknown_rule=error").is_err());
+    assert!(rules.set_from_str("function_times_missing=fatal").is_err());
 }
@@ -0,0 +1,232 @@
+use std::fs::read_to_string;
+
+use anyhow::{bail, Context, Result};
+use strict_yaml_rust::strict_yaml::Hash;
+use strict_yaml_rust::{StrictYaml, StrictYamlLoader};
+
+use super::ImportedFlight;
+use crate::flightlistgenerator::diagnostic::{Diagnostic, Diagnostics, Severity};
+use crate::flightlistgenerator::flight::ALL_KEYS;
+use crate::flightlistgenerator::sourcemap::Position;
+use crate::flightlistgenerator::utils::{format_unknown_keys, optional_string, unknown_keys};
+
+/// Keys of the mapping file
+const MAPPING_KEYS: &[&str] = &["delimiter", "columns", "values"];
+
+/// How the columns of a csv file give the keys of the yaml flights.
+/// The mapping is a yaml file :
+///
+/// ```yaml
+/// delimiter: ";"
+/// columns:
+///   date_start:
+///     - Date
+///     - Off block
+///   date_end:
+///     - Date
+///     - On block
+///   apt_departure_icao: From
+///   apt_arrival_icao: To
+///   immatriculation: Registration
+/// values:
+///   pic: SELF
+/// ```
+///
+/// delimiter : optional, a comma by default
+/// columns : the column of each key, several columns are joined
+///   with a space. An empty cell leaves the key out of the flight.
+/// values : optional, keys with the same value for every flight
+#[derive(Debug)]
+pub struct CsvMapping {
+    delimiter: u8,
+    columns: Vec<(String, Vec<String>)>,
+    values: Vec<(String, String)>,
+}
+
+impl CsvMapping {
+    pub fn from_yaml_file(file: &str) -> Result<Self> {
+        let content = read_to_string(file).with_context(|| format!("Cannot open file : {file}"))?;
+        let documents = StrictYamlLoader::load_from_str(&content)
+            .with_context(|| format!("Cannot parse the mapping file : {file}"))?;
+        let yaml = documents
+            .first()
+            .with_context(|| format!("Empty mapping file : {file}"))?;
+        CsvMapping::from_yaml(yaml).with_context(|| format!("In mapping file : {file}"))
+    }
+
+    pub fn from_yaml(yaml: &StrictYaml) -> Result<Self> {
+        let unknown = unknown_keys(yaml, &[MAPPING_KEYS]);
+        if !unknown.is_empty() {
+            bail!("Unknown keys : {}", format_unknown_keys(&unknown));
+        }
+        let delimiter = match optional_string(yaml, "delimiter") {
+            Some(delimiter) if delimiter.len() == 1 => delimiter.as_bytes()[0],
+            Some(delimiter) => bail!("[delimiter] must be one character : {delimiter:?}"),
+            None => b',',
+        };
+
+        let mut columns = Vec::new();
+        for (key, value) in flight_keys(yaml, "columns")? {
+            let value_columns = match value {
+                StrictYaml::String(column) => vec![column.to_string()],
+                StrictYaml::Array(value_columns) => value_columns
+                    .iter()
+                    .map(|column| column.as_str().map(|column| column.to_string()))
+                    .collect::<Option<Vec<_>>>()
+                    .with_context(|| format!("[columns] [{key}] must be a list of columns"))?,
+                _ => bail!("[columns] [{key}] must be a column or a list of columns"),
+            };
+            columns.push((key.to_string(), value_columns));
+        }
+        if columns.is_empty() {
+            bail!("[columns] missing, expected a mapping of flight keys to columns");
+        }
+
+        let mut values = Vec::new();
+        if !yaml["values"].is_badvalue() {
+            for (key, value) in flight_keys(yaml, "values")? {
+                let value = value
+                    .as_str()
+                    .with_context(|| format!("[values] [{key}] must be a string"))?;
+                values.push((key.to_string(), value.to_string()));
+            }
+        }
+        Ok(CsvMapping {
+            delimiter,
+            columns,
+            values,
+        })
+    }
+
+    /// Read the rows of a csv file with a header line.
+    /// A row which cannot be read goes to the diagnostics.
+    /// Returns an error if a column of the mapping is missing.
+    pub fn read_flights(
+        &self,
+        csv_file: &str,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Vec<ImportedFlight>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_path(csv_file)
+            .with_context(|| format!("Cannot open file : {csv_file}"))?;
+        let headers = reader
+            .headers()
+            .with_context(|| format!("Cannot read the header line of {csv_file}"))?
+            .clone();
+
+        // index of the columns of each key
+        let mut column_indexes = Vec::new();
+        for (key, columns) in self.columns.iter() {
+            let mut indexes = Vec::new();
+            for column in columns {
+                let index = headers
+                    .iter()
+                    .position(|header| header.trim() == column)
+                    .with_context(|| {
+                        format!(
+                            "column {column:?} of [{key}] not in {csv_file}, columns : {}",
+                            headers.iter().collect::<Vec<_>>().join(", ")
+                        )
+                    })?;
+                indexes.push(index);
+            }
+            column_indexes.push((key, indexes));
+        }
+
+        let mut flights = Vec::new();
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: e.to_string(),
+                        file: csv_file.to_string(),
+                        position: e.position().map(|position| Position {
+                            line: position.line() as usize,
+                            column: 1,
+                        }),
+                        flight_index: None,
+                        date_start: None,
+                        snippet: None,
+                    });
+                    continue;
+                }
+            };
+            let mut flight = Hash::new();
+            for (key, indexes) in column_indexes.iter() {
+                let value = indexes
+                    .iter()
+                    .filter_map(|index| record.get(*index))
+                    .map(|cell| cell.trim())
+                    .filter(|cell| !cell.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !value.is_empty() {
+                    flight.insert(
+                        StrictYaml::String(key.to_string()),
+                        StrictYaml::String(value),
+                    );
+                }
+            }
+            for (key, value) in self.values.iter() {
+                flight.insert(
+                    StrictYaml::String(key.to_string()),
+                    StrictYaml::String(value.to_string()),
+                );
+            }
+            flights.push(ImportedFlight {
+                line: record
+                    .position()
+                    .map(|position| position.line() as usize)
+                    .unwrap_or_default(),
+                yaml: StrictYaml::Hash(flight),
+                copilot_time: None,
+            });
+        }
+        Ok(flights)
+    }
+}
+
+/// The entries of a mapping whose keys are flight keys
+fn flight_keys<'a>(yaml: &'a StrictYaml, key: &str) -> Result<Vec<(&'a str, &'a StrictYaml)>> {
+    let StrictYaml::Hash(hash) = &yaml[key] else {
+        bail!("[{key}] must be a mapping of flight keys");
+    };
+    let unknown = unknown_keys(&yaml[key], ALL_KEYS);
+    if !unknown.is_empty() {
+        bail!("In [{key}] : {}", format_unknown_keys(&unknown));
+    }
+    Ok(hash
+        .iter()
+        .filter_map(|(key, value)| key.as_str().map(|key| (key, value)))
+        .collect())
+}
+
+#[test]
+fn csv_mapping_rows() {
+    let mapping = StrictYamlLoader::load_from_str(
+        "delimiter: \";\"\ncolumns:\n  date_start:\n    - Date\n    - Off\n  comment: Remarks\nvalues:\n  pic: SELF\n",
+    )
+    .unwrap();
+    let mapping = CsvMapping::from_yaml(&mapping[0]).unwrap();
+    let csv_file = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests/mapping.csv");
+    let mut diagnostics = Diagnostics::default();
+    let flights = mapping.read_flights(csv_file, &mut diagnostics).unwrap();
+    assert!(diagnostics.is_empty());
+    assert_eq!(flights.len(), 2);
+    assert_eq!(flights[0].line, 2);
+    assert_eq!(
+        flights[0].yaml["date_start"].as_str(),
+        Some("2001-01-01 05:00")
+    );
+    assert!(flights[0].yaml["comment"].is_badvalue());
+    assert_eq!(flights[1].yaml["comment"].as_str(), Some("solo"));
+    assert_eq!(flights[1].yaml["pic"].as_str(), Some("SELF"));
+
+    let bad_mapping = StrictYamlLoader::load_from_str("columns:\n  date_strat: Date\n").unwrap();
+    let error = CsvMapping::from_yaml(&bad_mapping[0]).unwrap_err();
+    assert!(error.to_string().contains("did you mean [date_start]"));
+}
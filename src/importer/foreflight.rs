@@ -0,0 +1,286 @@
+//! Import of the csv export of ForeFlight, the layout LogTen Pro
+//! also reads and writes for its ForeFlight exchange.
+//!
+//! The ForeFlight file has two tables, each one after a title line :
+//! "Aircraft Table" then "Flights Table". A file with only the header
+//! line of the flights table is also accepted.
+//! The columns used are :
+//! - aircraft table : AircraftID, TypeCode, Model
+//! - flights table : Date (YYYY-MM-DD), AircraftID, From, To,
+//!   TimeOut, TimeOff, TimeOn, TimeIn (HH:MM or HHMM),
+//!   TotalTime, PIC, SIC, Night, ActualInstrument, DualGiven,
+//!   DualReceived, SimulatedFlight (decimal hours),
+//!   DayTakeoffs, NightTakeoffs, DayLandingsFullStop,
+//!   NightLandingsFullStop, Approach1 to Approach6,
+//!   Person1 to Person6 (name;role;email), PilotComments.
+//!
+//! The block times TimeOut and TimeIn are used, or else TimeOff and TimeOn.
+//! The PIC is the person with the role PIC, or SELF when PIC time is logged.
+//! The copilot time is computed by zenjep from the multipilot aircraft,
+//! a SIC time different from it is reported.
+//! A row with simulated flight time and no total time is a simulator session.
+//! The aircraft models and airports must be known, from the catalogs
+//! or from previous flights of the logbook.
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use csv::StringRecord;
+use strict_yaml_rust::strict_yaml::Hash;
+
+use super::ImportedFlight;
+use super::{insert, insert_count, insert_dates, insert_duration, map_rows, CsvRow};
+use crate::flightlistgenerator::diagnostic::Diagnostics;
+
+pub fn read_flights(file: &str, diagnostics: &mut Diagnostics) -> Result<Vec<ImportedFlight>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .has_headers(false)
+        .from_path(file)
+        .with_context(|| format!("Cannot open file : {file}"))?;
+    let records = reader
+        .records()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("Cannot read the rows of {file}"))?;
+    let tables = Tables::from_records(&records)
+        .with_context(|| format!("{file} is not a ForeFlight export"))?;
+
+    // aircraft id -> model
+    let mut models = HashMap::new();
+    if let Some((headers, aircraft_records)) = tables.aircraft {
+        for record in aircraft_records {
+            let row = CsvRow { headers, record };
+            if let (Some(aircraft_id), Some(model)) = (
+                row.get("AircraftID"),
+                row.get("TypeCode").or_else(|| row.get("Model")),
+            ) {
+                models.insert(aircraft_id.to_string(), model.to_string());
+            }
+        }
+    }
+    let (headers, flight_records) = tables.flights;
+    Ok(map_rows(
+        file,
+        headers,
+        flight_records,
+        |row| flight_from_row(row, &models),
+        diagnostics,
+    ))
+}
+
+/// Header and rows of the tables of the file
+struct Tables<'a> {
+    aircraft: Option<(&'a StringRecord, &'a [StringRecord])>,
+    flights: (&'a StringRecord, &'a [StringRecord]),
+}
+
+impl<'a> Tables<'a> {
+    fn from_records(records: &'a [StringRecord]) -> Result<Self> {
+        let title = |title: &str| {
+            records
+                .iter()
+                .position(|record| record.get(0).map(|cell| cell.trim()) == Some(title))
+        };
+        let Some(flights_title) = title("Flights Table") else {
+            // only the flights table, with its header line
+            let Some((headers, flight_records)) = records.split_first() else {
+                bail!("empty file");
+            };
+            check_flights_header(headers)?;
+            return Ok(Tables {
+                aircraft: None,
+                flights: (headers, flight_records),
+            });
+        };
+        let Some((headers, flight_records)) = records[flights_title + 1..].split_first() else {
+            bail!("header line of the flights table missing");
+        };
+        check_flights_header(headers)?;
+        let aircraft = title("Aircraft Table")
+            .filter(|aircraft_title| *aircraft_title < flights_title)
+            .and_then(|aircraft_title| records[aircraft_title + 1..flights_title].split_first());
+        Ok(Tables {
+            aircraft,
+            flights: (headers, flight_records),
+        })
+    }
+}
+
+fn check_flights_header(headers: &StringRecord) -> Result<()> {
+    for column in ["Date", "AircraftID", "TotalTime"] {
+        if !headers.iter().any(|header| header.trim() == column) {
+            bail!("column {column} missing in the flights table");
+        }
+    }
+    Ok(())
+}
+
+fn flight_from_row(row: &CsvRow, models: &HashMap<String, String>) -> Result<(Hash, Option<i64>)> {
+    let mut flight = Hash::new();
+    let date = row.get("Date").context("[Date] missing")?;
+    let aircraft_id = row.get("AircraftID").context("[AircraftID] missing")?;
+    let model = row
+        .get("TypeCode")
+        .or_else(|| models.get(aircraft_id).map(|model| model.as_str()));
+    let total_time = row.decimal_hours("TotalTime")?;
+    let simulated_time = row.decimal_hours("SimulatedFlight")?;
+    let remarks = remarks(row);
+
+    if total_time == 0 && simulated_time > 0 {
+        let time = row
+            .get("TimeOut")
+            .or_else(|| row.get("TimeOff"))
+            .unwrap_or("0000");
+        insert(&mut flight, "is_sim", "true");
+        insert(
+            &mut flight,
+            "sim_date",
+            format!("{date} {}", super::time_of_day(time)?),
+        );
+        insert(&mut flight, "sim_type", model.unwrap_or(aircraft_id));
+        insert_duration(&mut flight, "sim_total_time", simulated_time);
+        if !remarks.is_empty() {
+            insert(&mut flight, "comment", remarks);
+        }
+        return Ok((flight, None));
+    }
+
+    let (departure, arrival) = match (
+        row.get("TimeOut"),
+        row.get("TimeIn"),
+        row.get("TimeOff"),
+        row.get("TimeOn"),
+    ) {
+        (Some(departure), Some(arrival), _, _) | (_, _, Some(departure), Some(arrival)) => {
+            (departure, arrival)
+        }
+        _ => bail!("[TimeOut] and [TimeIn], or [TimeOff] and [TimeOn], missing"),
+    };
+    insert_dates(&mut flight, date, departure, arrival)?;
+    let pic_time = row.decimal_hours("PIC")?;
+    insert_duration(&mut flight, "duration_total", total_time);
+    insert_duration(&mut flight, "duration_pic", pic_time);
+    insert(
+        &mut flight,
+        "apt_departure_icao",
+        row.get("From").context("[From] missing")?,
+    );
+    insert(
+        &mut flight,
+        "apt_arrival_icao",
+        row.get("To").context("[To] missing")?,
+    );
+    insert(&mut flight, "immatriculation", aircraft_id);
+    if let Some(model) = model {
+        insert(&mut flight, "acmodel", model);
+    }
+    let pic = match (person_with_role(row, "PIC"), pic_time) {
+        (Some(pic), _) => pic,
+        (None, 1..) => "SELF",
+        (None, _) => bail!("no person with the role PIC, and no PIC time"),
+    };
+    insert(&mut flight, "pic", pic);
+    insert_count(&mut flight, "takeoff_day", row.count("DayTakeoffs")?);
+    insert_count(&mut flight, "takeoff_night", row.count("NightTakeoffs")?);
+    insert_count(
+        &mut flight,
+        "landing_day",
+        row.count("DayLandingsFullStop")?,
+    );
+    insert_count(
+        &mut flight,
+        "landing_night",
+        row.count("NightLandingsFullStop")?,
+    );
+    insert_duration(
+        &mut flight,
+        "oc_time_ifr",
+        row.decimal_hours("ActualInstrument")?,
+    );
+    insert_duration(&mut flight, "oc_time_night", row.decimal_hours("Night")?);
+    insert_duration(&mut flight, "dual_time", row.decimal_hours("DualReceived")?);
+    insert_duration(
+        &mut flight,
+        "instructor_time",
+        row.decimal_hours("DualGiven")?,
+    );
+    if !remarks.is_empty() {
+        insert(&mut flight, "comment", remarks);
+    }
+    let copilot_time = match row.get("SIC") {
+        Some(_) => Some(row.decimal_hours("SIC")?),
+        None => None,
+    };
+    Ok((flight, copilot_time))
+}
+
+/// The name of the first person with the role, in Person1 to Person6
+fn person_with_role<'a>(row: &'a CsvRow, role: &str) -> Option<&'a str> {
+    (1..=6)
+        .filter_map(|index| row.get(&format!("Person{index}")))
+        .find_map(|person| {
+            let mut fields = person.split(';').map(|field| field.trim());
+            let name = fields.next()?;
+            (fields.next()? == role).then_some(name)
+        })
+}
+
+/// The pilot comments, followed by the approaches if any.
+/// An approach is count;type;runway;airport;comments
+fn remarks(row: &CsvRow) -> String {
+    let approaches: Vec<String> = (1..=6)
+        .filter_map(|index| row.get(&format!("Approach{index}")))
+        .map(|approach| {
+            let fields: Vec<&str> = approach.split(';').map(|field| field.trim()).collect();
+            match (fields.first(), fields.get(1), fields.get(3)) {
+                (Some(count), Some(kind), Some(airport)) => {
+                    format!("{count} {kind} {airport}").trim().to_string()
+                }
+                _ => approach.to_string(),
+            }
+        })
+        .collect();
+    match (row.get("PilotComments"), approaches.is_empty()) {
+        (Some(remarks), true) => remarks.to_string(),
+        (Some(remarks), false) => format!("{remarks} (approaches : {})", approaches.join(", ")),
+        (None, false) => format!("approaches : {}", approaches.join(", ")),
+        (None, true) => String::new(),
+    }
+}
+
+#[test]
+fn foreflight_sample() {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/import/foreflight.csv");
+    let mut diagnostics = Diagnostics::default();
+    let flights = read_flights(file, &mut diagnostics).unwrap();
+    assert_eq!(flights.len(), 4);
+
+    let flight = &flights[0].yaml;
+    assert_eq!(flight["date_start"].as_str(), Some("2022-05-14 09:05"));
+    assert_eq!(flight["date_end"].as_str(), Some("2022-05-14 10:35"));
+    assert_eq!(flight["duration_total"].as_str(), Some("01:30"));
+    assert_eq!(flight["duration_pic"].as_str(), Some("01:30"));
+    assert_eq!(flight["immatriculation"].as_str(), Some("F-HABC"));
+    assert_eq!(flight["acmodel"].as_str(), Some("C172"));
+    assert_eq!(flight["pic"].as_str(), Some("SELF"));
+    assert_eq!(flight["takeoff_day"].as_str(), Some("1"));
+    assert_eq!(
+        flight["comment"].as_str(),
+        Some("local flight (approaches : 1 ILS OR LOC LFLY)")
+    );
+
+    // dual flight, the instructor is the PIC
+    let flight = &flights[1].yaml;
+    assert_eq!(flight["pic"].as_str(), Some("Jane Smith"));
+    assert_eq!(flight["dual_time"].as_str(), Some("01:00"));
+    assert!(flight["duration_pic"].is_badvalue());
+
+    let simulator = &flights[2].yaml;
+    assert_eq!(simulator["is_sim"].as_str(), Some("true"));
+    assert_eq!(simulator["sim_type"].as_str(), Some("FNPT2"));
+    assert_eq!(simulator["sim_total_time"].as_str(), Some("01:15"));
+
+    // the row without any time
+    let diagnostics: Vec<_> = diagnostics.iter().collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.contains("[TimeOut] and [TimeIn]"));
+}
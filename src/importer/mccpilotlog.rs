@@ -0,0 +1,197 @@
+//! Import of the csv export of mccPILOTLOG.
+//!
+//! One flight per row, the columns used are :
+//! mcc_DATE (YYYY-MM-DD), AC_ISSIM, AF_DEP, TIME_DEP, AF_ARR, TIME_ARR
+//! (ICAO codes and HH:MM), AC_MODEL, AC_REG, PILOT1_NAME,
+//! TIME_TOTAL, TIME_TOTALSIM, TIME_PIC, TIME_PICUS, TIME_SIC, TIME_DUAL,
+//! TIME_INSTRUCTOR, TIME_NIGHT, TIME_IFR (minutes),
+//! TO_DAY, TO_NIGHT, LDG_DAY, LDG_NIGHT, APP_1, APP_2, APP_3, REMARKS.
+//!
+//! The copilot time is computed by zenjep from the multipilot aircraft,
+//! a TIME_SIC different from it is reported. The approaches have no
+//! Jeppesen column, they are kept in the comment.
+//! The aircraft models and airports must be known, from the catalogs
+//! or from previous flights of the logbook.
+use anyhow::{Context, Result};
+use strict_yaml_rust::strict_yaml::Hash;
+
+use super::ImportedFlight;
+use super::{insert, insert_count, insert_dates, insert_duration, map_rows, CsvRow};
+use crate::flightlistgenerator::diagnostic::Diagnostics;
+
+pub fn read_flights(file: &str, diagnostics: &mut Diagnostics) -> Result<Vec<ImportedFlight>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(file)
+        .with_context(|| format!("Cannot open file : {file}"))?;
+    let headers = reader
+        .headers()
+        .with_context(|| format!("Cannot read the header line of {file}"))?
+        .clone();
+    if !headers.iter().any(|header| header.trim() == "mcc_DATE") {
+        anyhow::bail!("{file} is not a mccPILOTLOG export, column mcc_DATE missing");
+    }
+    let records = reader
+        .records()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("Cannot read the rows of {file}"))?;
+    Ok(map_rows(
+        file,
+        &headers,
+        &records,
+        flight_from_row,
+        diagnostics,
+    ))
+}
+
+fn flight_from_row(row: &CsvRow) -> Result<(Hash, Option<i64>)> {
+    let mut flight = Hash::new();
+    let date = row.get("mcc_DATE").context("[mcc_DATE] missing")?;
+    let remarks = remarks(row);
+
+    if row
+        .get("AC_ISSIM")
+        .is_some_and(|is_sim| is_sim.eq_ignore_ascii_case("true"))
+    {
+        insert(&mut flight, "is_sim", "true");
+        insert(
+            &mut flight,
+            "sim_date",
+            format!(
+                "{date} {}",
+                super::time_of_day(row.get("TIME_DEP").unwrap_or("0000"))?
+            ),
+        );
+        insert(
+            &mut flight,
+            "sim_type",
+            row.get("AC_MODEL")
+                .context("[AC_MODEL] missing for the simulator")?,
+        );
+        let sim_time = match row.minutes("TIME_TOTALSIM")? {
+            0 => row.minutes("TIME_TOTAL")?,
+            sim_time => sim_time,
+        };
+        insert_duration(&mut flight, "sim_total_time", sim_time);
+        if !remarks.is_empty() {
+            insert(&mut flight, "comment", remarks);
+        }
+        return Ok((flight, None));
+    }
+
+    insert_dates(
+        &mut flight,
+        date,
+        row.get("TIME_DEP").context("[TIME_DEP] missing")?,
+        row.get("TIME_ARR").context("[TIME_ARR] missing")?,
+    )?;
+    insert_duration(&mut flight, "duration_total", row.minutes("TIME_TOTAL")?);
+    insert_duration(
+        &mut flight,
+        "duration_pic",
+        row.minutes("TIME_PIC")? + row.minutes("TIME_PICUS")?,
+    );
+    insert(
+        &mut flight,
+        "apt_departure_icao",
+        row.get("AF_DEP").context("[AF_DEP] missing")?,
+    );
+    insert(
+        &mut flight,
+        "apt_arrival_icao",
+        row.get("AF_ARR").context("[AF_ARR] missing")?,
+    );
+    insert(
+        &mut flight,
+        "immatriculation",
+        row.get("AC_REG").context("[AC_REG] missing")?,
+    );
+    if let Some(model) = row.get("AC_MODEL") {
+        insert(&mut flight, "acmodel", model);
+    }
+    insert(
+        &mut flight,
+        "pic",
+        row.get("PILOT1_NAME").context("[PILOT1_NAME] missing")?,
+    );
+    insert_count(&mut flight, "takeoff_day", row.count("TO_DAY")?);
+    insert_count(&mut flight, "takeoff_night", row.count("TO_NIGHT")?);
+    insert_count(&mut flight, "landing_day", row.count("LDG_DAY")?);
+    insert_count(&mut flight, "landing_night", row.count("LDG_NIGHT")?);
+    insert_duration(&mut flight, "oc_time_ifr", row.minutes("TIME_IFR")?);
+    insert_duration(&mut flight, "oc_time_night", row.minutes("TIME_NIGHT")?);
+    insert_duration(&mut flight, "dual_time", row.minutes("TIME_DUAL")?);
+    insert_duration(
+        &mut flight,
+        "instructor_time",
+        row.minutes("TIME_INSTRUCTOR")?,
+    );
+    if !remarks.is_empty() {
+        insert(&mut flight, "comment", remarks);
+    }
+    let copilot_time = match row.get("TIME_SIC") {
+        Some(_) => Some(row.minutes("TIME_SIC")?),
+        None => None,
+    };
+    Ok((flight, copilot_time))
+}
+
+/// The remarks, followed by the approaches if any
+fn remarks(row: &CsvRow) -> String {
+    let approaches: Vec<&str> = ["APP_1", "APP_2", "APP_3"]
+        .iter()
+        .filter_map(|column| row.get(column))
+        .collect();
+    match (row.get("REMARKS"), approaches.is_empty()) {
+        (Some(remarks), true) => remarks.to_string(),
+        (Some(remarks), false) => format!("{remarks} (approaches : {})", approaches.join(", ")),
+        (None, false) => format!("approaches : {}", approaches.join(", ")),
+        (None, true) => String::new(),
+    }
+}
+
+#[test]
+fn mccpilotlog_sample() {
+    let file = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/import/mccpilotlog.csv"
+    );
+    let mut diagnostics = Diagnostics::default();
+    let flights = read_flights(file, &mut diagnostics).unwrap();
+    assert_eq!(flights.len(), 4);
+
+    let flight = &flights[0].yaml;
+    assert_eq!(flights[0].line, 2);
+    assert_eq!(flight["date_start"].as_str(), Some("2021-03-01 06:10"));
+    assert_eq!(flight["date_end"].as_str(), Some("2021-03-01 07:35"));
+    assert_eq!(flight["duration_total"].as_str(), Some("01:25"));
+    assert_eq!(flight["apt_departure_icao"].as_str(), Some("LFLS"));
+    assert_eq!(flight["immatriculation"].as_str(), Some("F-GABC"));
+    assert_eq!(flight["acmodel"].as_str(), Some("A320"));
+    assert_eq!(flight["oc_time_ifr"].as_str(), Some("01:25"));
+    assert_eq!(flight["landing_day"].as_str(), Some("1"));
+    assert!(flight["duration_pic"].is_badvalue());
+    assert_eq!(
+        flight["comment"].as_str(),
+        Some("line check (approaches : ILS)")
+    );
+
+    // landing after midnight
+    assert_eq!(
+        flights[1].yaml["date_end"].as_str(),
+        Some("2021-03-02 00:20")
+    );
+    assert_eq!(flights[1].yaml["oc_time_night"].as_str(), Some("01:00"));
+
+    let simulator = &flights[2].yaml;
+    assert_eq!(simulator["is_sim"].as_str(), Some("true"));
+    assert_eq!(simulator["sim_date"].as_str(), Some("2021-03-05 09:00"));
+    assert_eq!(simulator["sim_type"].as_str(), Some("A320"));
+    assert_eq!(simulator["sim_total_time"].as_str(), Some("04:00"));
+
+    // the row without arrival time
+    let diagnostics: Vec<_> = diagnostics.iter().collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].position.unwrap().line, 4);
+    assert!(diagnostics[0].message.contains("[TIME_ARR] missing"));
+}
@@ -0,0 +1,391 @@
+//! Import the flights of other logbooks into the yaml logbook.
+//!
+//! Each importer reads its file into yaml flight elements,
+//! which are then checked like the flights of the logbook.
+pub mod csvmapping;
+pub mod foreflight;
+pub mod mccpilotlog;
+mod yamlwriter;
+
+use anyhow::{bail, Context, Result};
+use csv::StringRecord;
+use strict_yaml_rust::strict_yaml::Hash;
+use strict_yaml_rust::StrictYaml;
+use time::Date;
+
+use crate::flightlistgenerator::diagnostic::{Diagnostic, Diagnostics, Severity};
+use crate::flightlistgenerator::flight::SIMULATOR_KEYS;
+use crate::flightlistgenerator::sourcemap::Position;
+use crate::flightlistgenerator::{FlightList, LoadOptions};
+pub use yamlwriter::format_flights;
+
+/// Keys of an aircraft model definition in a flight
+const AIRCRAFT_MODEL_KEYS: &[&str] = &[
+    "actype",
+    "nb_engines",
+    "mtow",
+    "multipilot",
+    "cat1",
+    "cat2",
+    "cat3",
+];
+
+/// A flight read from another logbook, as a yaml flight element
+/// line : line of the flight in the imported file
+/// copilot_time : the copilot time of the other logbook, in minutes.
+///   zenjep computes it, a different value is reported.
+#[derive(Debug)]
+pub struct ImportedFlight {
+    pub line: usize,
+    pub yaml: StrictYaml,
+    pub copilot_time: Option<i64>,
+}
+
+/// Export formats of other logbook tools, with a fixed layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// csv export of mccPILOTLOG
+    MccPilotLog,
+    /// csv export of ForeFlight, also exchanged with LogTen Pro
+    ForeFlight,
+}
+
+impl ExportFormat {
+    /// Read the flights of an export file.
+    /// The rows which cannot be mapped go to the diagnostics.
+    pub fn read_flights(
+        &self,
+        file: &str,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Vec<ImportedFlight>> {
+        match self {
+            ExportFormat::MccPilotLog => mccpilotlog::read_flights(file, diagnostics),
+            ExportFormat::ForeFlight => foreflight::read_flights(file, diagnostics),
+        }
+    }
+}
+
+/// Check the imported flights as if they were appended to the flight list,
+/// the problems go to the diagnostics.
+/// Returns the yaml elements of the valid flights, the aircraft and airport
+/// definitions are only kept for their first occurrence.
+pub fn import_flights(
+    imported_flights: &[ImportedFlight],
+    file: &str,
+    flight_list: &mut FlightList,
+    options: &LoadOptions,
+    diagnostics: &mut Diagnostics,
+) -> Vec<StrictYaml> {
+    let mut flights = Vec::new();
+    for (index, imported_flight) in imported_flights.iter().enumerate() {
+        let yaml = without_known_definitions(&imported_flight.yaml, flight_list);
+        let position = Position {
+            line: imported_flight.line,
+            column: 1,
+        };
+        if flight_list.add_imported_flight(&yaml, file, index, position, options, diagnostics) {
+            if let (Some(copilot_time), Some(flight)) =
+                (imported_flight.copilot_time, flight_list.flights.last())
+            {
+                let computed = flight.copilot_time.0.whole_minutes();
+                if computed != copilot_time {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "copilot time {} in the export but {} in zenjep, which logs the total time as copilot time on a multipilot aircraft without pic time",
+                            hours_minutes(copilot_time),
+                            hours_minutes(computed),
+                        ),
+                        file: file.to_string(),
+                        position: Some(position),
+                        flight_index: Some(index),
+                        date_start: yaml["date_start"].as_str().map(|date| date.to_string()),
+                        snippet: None,
+                    });
+                }
+            }
+            flights.push(yaml);
+        }
+    }
+    flights
+}
+
+/// The yaml flight without the definitions already in the flight list :
+/// aircraft model, immatriculation and airports.
+/// A simulator session only keeps the simulator keys.
+fn without_known_definitions(yaml: &StrictYaml, flight_list: &FlightList) -> StrictYaml {
+    let StrictYaml::Hash(hash) = yaml else {
+        return yaml.clone();
+    };
+    let mut removed_keys: Vec<String> = Vec::new();
+    if yaml["is_sim"].as_str() == Some("true") {
+        removed_keys.extend(
+            hash.keys()
+                .filter_map(|key| key.as_str())
+                .filter(|key| !SIMULATOR_KEYS.contains(key))
+                .map(|key| key.to_string()),
+        );
+    }
+
+    // aircraft
+    if let Some(immatriculation) = yaml["immatriculation"].as_str() {
+        if flight_list.aircrafts.has_immatriculation(immatriculation) {
+            removed_keys.push("acmodel".to_string());
+            removed_keys.extend(AIRCRAFT_MODEL_KEYS.iter().map(|key| key.to_string()));
+        } else if yaml["acmodel"]
+            .as_str()
+            .is_some_and(|acmodel| flight_list.aircrafts.has_model(acmodel))
+        {
+            removed_keys.extend(AIRCRAFT_MODEL_KEYS.iter().map(|key| key.to_string()));
+        }
+    }
+
+    // airports : only the first known code, the codes of the
+    // reference data are known
+    for prefix in ["apt_departure", "apt_arrival"] {
+        let iata = yaml[format!("{prefix}_iata").as_str()].as_str();
+        let icao = yaml[format!("{prefix}_icao").as_str()].as_str();
+        let name = yaml[format!("{prefix}_name").as_str()].as_str();
+        let airports = &flight_list.airports;
+        let known_key = if iata.is_some_and(|iata| iata != "???" && airports.knows_iata(iata)) {
+            Some("iata")
+        } else if icao.is_some_and(|icao| icao != "????" && airports.knows_icao(icao)) {
+            Some("icao")
+        } else if name.is_some_and(|name| airports.has_name(name)) {
+            Some("name")
+        } else {
+            None
+        };
+        if let Some(known_key) = known_key {
+            removed_keys.extend(
+                ["iata", "icao", "name"]
+                    .iter()
+                    .filter(|key| **key != known_key)
+                    .map(|key| format!("{prefix}_{key}")),
+            );
+        }
+    }
+
+    let mut reduced = Hash::new();
+    for (key, value) in hash.iter() {
+        if !key
+            .as_str()
+            .is_some_and(|key| removed_keys.iter().any(|removed| removed == key))
+        {
+            reduced.insert(key.clone(), value.clone());
+        }
+    }
+    StrictYaml::Hash(reduced)
+}
+
+/// A csv record with access to its cells by column name
+struct CsvRow<'a> {
+    headers: &'a StringRecord,
+    record: &'a StringRecord,
+}
+
+impl CsvRow<'_> {
+    /// The trimmed cell of the column, None if the cell is empty
+    fn get(&self, column: &str) -> Option<&str> {
+        let index = self
+            .headers
+            .iter()
+            .position(|header| header.trim() == column)?;
+        self.record
+            .get(index)
+            .map(|cell| cell.trim())
+            .filter(|cell| !cell.is_empty())
+    }
+
+    fn line(&self) -> usize {
+        self.record
+            .position()
+            .map(|position| position.line() as usize)
+            .unwrap_or_default()
+    }
+
+    /// A cell holding a number of minutes, 0 if empty
+    fn minutes(&self, column: &str) -> Result<i64> {
+        match self.get(column) {
+            Some(minutes) => minutes
+                .parse::<i64>()
+                .with_context(|| format!("[{column}] is not a number of minutes : {minutes}")),
+            None => Ok(0),
+        }
+    }
+
+    /// A cell holding decimal hours, as minutes, 0 if empty
+    fn decimal_hours(&self, column: &str) -> Result<i64> {
+        match self.get(column) {
+            Some(hours) => {
+                let hours = hours
+                    .parse::<f64>()
+                    .with_context(|| format!("[{column}] is not a number of hours : {hours}"))?;
+                Ok((hours * 60.0).round() as i64)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// A cell holding a count, 0 if empty
+    fn count(&self, column: &str) -> Result<u32> {
+        match self.get(column) {
+            Some(count) => count
+                .parse::<u32>()
+                .with_context(|| format!("[{column}] is not a number : {count}")),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Read the rows of an export file and map each one to a flight,
+/// with its copilot time if the export has one.
+/// A row which cannot be mapped goes to the diagnostics.
+fn map_rows(
+    file: &str,
+    headers: &StringRecord,
+    records: &[StringRecord],
+    map_row: impl Fn(&CsvRow) -> Result<(Hash, Option<i64>)>,
+    diagnostics: &mut Diagnostics,
+) -> Vec<ImportedFlight> {
+    let mut flights = Vec::new();
+    for record in records {
+        let row = CsvRow { headers, record };
+        match map_row(&row) {
+            Ok((flight, copilot_time)) => flights.push(ImportedFlight {
+                line: row.line(),
+                yaml: StrictYaml::Hash(flight),
+                copilot_time,
+            }),
+            Err(e) => diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("cannot map the row : {e:#}"),
+                file: file.to_string(),
+                position: Some(Position {
+                    line: row.line(),
+                    column: 1,
+                }),
+                flight_index: None,
+                date_start: None,
+                snippet: None,
+            }),
+        }
+    }
+    flights
+}
+
+/// Add a key to a yaml flight
+fn insert(flight: &mut Hash, key: &str, value: impl Into<String>) {
+    flight.insert(
+        StrictYaml::String(key.to_string()),
+        StrictYaml::String(value.into()),
+    );
+}
+
+/// Add a duration in minutes to a yaml flight, if not zero
+fn insert_duration(flight: &mut Hash, key: &str, minutes: i64) {
+    if minutes != 0 {
+        insert(flight, key, hours_minutes(minutes));
+    }
+}
+
+/// A number of minutes as HH:MM
+fn hours_minutes(minutes: i64) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Add a count to a yaml flight, if not zero
+fn insert_count(flight: &mut Hash, key: &str, count: u32) {
+    if count != 0 {
+        insert(flight, key, count.to_string());
+    }
+}
+
+/// Add [date_start] and [date_end] to a yaml flight.
+/// date : YYYY-MM-DD, times : HH:MM or HHMM.
+/// A flight landing before its departure time lands the next day.
+fn insert_dates(flight: &mut Hash, date: &str, departure: &str, arrival: &str) -> Result<()> {
+    let format = time::format_description::parse("[year]-[month]-[day]")?;
+    let date_departure = Date::parse(date, &format)
+        .with_context(|| format!("bad date {date}, expected YYYY-MM-DD"))?;
+    let departure = time_of_day(departure)?;
+    let arrival = time_of_day(arrival)?;
+    let date_arrival = if arrival < departure {
+        date_departure
+            .next_day()
+            .with_context(|| format!("bad date {date}"))?
+    } else {
+        date_departure
+    };
+    insert(
+        flight,
+        "date_start",
+        format!("{date_departure} {departure}"),
+    );
+    insert(flight, "date_end", format!("{date_arrival} {arrival}"));
+    Ok(())
+}
+
+/// A time of day as HH:MM, from HH:MM, H:MM or HHMM
+fn time_of_day(time: &str) -> Result<String> {
+    let digits: String = time.chars().filter(|c| *c != ':').collect();
+    if !(3..=4).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        bail!("bad time {time}, expected HH:MM");
+    }
+    let (hours, minutes) = digits.split_at(digits.len() - 2);
+    let (hours, minutes) = (hours.parse::<u8>()?, minutes.parse::<u8>()?);
+    if hours > 23 || minutes > 59 {
+        bail!("bad time {time}, expected HH:MM");
+    }
+    Ok(format!("{hours:02}:{minutes:02}"))
+}
+
+#[test]
+fn copilot_time_of_the_exports() {
+    // the catalogs of the samples, next to a logbook not created yet
+    let logbook = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/import/logbook.yaml");
+    for (format, file, line) in [
+        (
+            ExportFormat::ForeFlight,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/samples/import/foreflight.csv"),
+            14,
+        ),
+        (
+            ExportFormat::MccPilotLog,
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/samples/import/mccpilotlog.csv"
+            ),
+            6,
+        ),
+    ] {
+        let mut diagnostics = Diagnostics::default();
+        let imported_flights = format.read_flights(file, &mut diagnostics).unwrap();
+        let (mut flight_list, _) =
+            FlightList::with_catalogs(logbook, &LoadOptions::default()).unwrap();
+        let flights = import_flights(
+            &imported_flights,
+            file,
+            &mut flight_list,
+            &LoadOptions::default(),
+            &mut diagnostics,
+        );
+        assert_eq!(flights.len(), 4, "{file}");
+        // the copilot of a single pilot aircraft
+        let copilot: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message.starts_with("copilot time"))
+            .map(|diagnostic| {
+                (
+                    diagnostic.severity,
+                    diagnostic.position.map(|position| position.line),
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(copilot.len(), 1, "{file} : {copilot:?}");
+        assert_eq!(copilot[0].0, Severity::Warning);
+        assert_eq!(copilot[0].1, Some(line));
+        assert!(copilot[0].2.contains("01:00 in the export but 00:00"));
+    }
+}
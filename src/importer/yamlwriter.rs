@@ -0,0 +1,67 @@
+use strict_yaml_rust::StrictYaml;
+
+/// Format yaml flight elements like the logbook files :
+/// a yaml vector with one paragraph per flight
+pub fn format_flights(flights: &[StrictYaml]) -> String {
+    let mut formatted = String::new();
+    for flight in flights {
+        if !formatted.is_empty() {
+            formatted.push('\n');
+        }
+        if let StrictYaml::Hash(hash) = flight {
+            let mut first_key = true;
+            for (key, value) in hash.iter() {
+                if let (Some(key), Some(value)) = (key.as_str(), value.as_str()) {
+                    let indent = if first_key { "- " } else { "  " };
+                    formatted.push_str(&format!("{indent}{key}: {}\n", format_value(value)));
+                    first_key = false;
+                }
+            }
+        }
+    }
+    formatted
+}
+
+/// A plain scalar when it cannot be misread,
+/// otherwise a double quoted string
+fn format_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || " _.-/()".contains(c))
+        && !value.starts_with('-');
+    if plain {
+        value.to_string()
+    } else {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("\"{escaped}\"")
+    }
+}
+
+#[test]
+fn formatted_flights_read_back() {
+    use strict_yaml_rust::strict_yaml::Hash;
+    use strict_yaml_rust::StrictYamlLoader;
+
+    let mut flight = Hash::new();
+    for (key, value) in [
+        ("date_start", "2001-01-01 05:00"),
+        ("pic", "JOHN DOE"),
+        ("comment", "first \"solo\" : - # ok\nsecond line"),
+        ("apt_departure_name", "-"),
+    ] {
+        flight.insert(
+            StrictYaml::String(key.to_string()),
+            StrictYaml::String(value.to_string()),
+        );
+    }
+    let flights = vec![StrictYaml::Hash(flight.clone()), StrictYaml::Hash(flight)];
+    let formatted = format_flights(&flights);
+    assert!(formatted.starts_with("- date_start: \"2001-01-01 05:00\"\n  pic: JOHN DOE\n"));
+    let read_back = StrictYamlLoader::load_from_str(&formatted).unwrap();
+    assert_eq!(read_back[0], StrictYaml::Array(flights));
+}
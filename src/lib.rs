@@ -1,4 +1,7 @@
 pub mod applicationstate;
+pub mod commands;
+pub mod exporter;
 pub mod flightlistgenerator;
+pub mod importer;
 pub mod pages;
 pub mod renderable;
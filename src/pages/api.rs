@@ -0,0 +1,256 @@
+//! JSON API, version 1, under /api/v1.
+//!
+//! Every route takes the parameters of the filter form of /filters,
+//! the flights are filtered with them and not with the filter of the pages.
+//! The durations are in minutes, the dates are YYYY-MM-DD HH:MM.
+//! An error is an object with the message in error, and the problems of
+//! the logbook in diagnostics. An invalid parameter is a 400 error.
+//! When the logbook has been changed with errors, its last version without
+//! errors is served with the number of errors in the X-Logbook-Errors header.
+use crate::applicationstate::AppState;
+use crate::commands::check::DiagnosticJson;
+use crate::flightlistgenerator::flight::Flight;
+use crate::flightlistgenerator::flighttime::TimeBase;
+use crate::flightlistgenerator::previousexperience::PreviousExperience;
+use crate::flightlistgenerator::{FlightList, LoadFailure};
+use crate::renderable::aircrafts::AircraftsHtml;
+use crate::renderable::filters::{FlightFilter, RawFilterParams};
+use crate::renderable::logbook::JeppesenBookShelfHtml;
+use crate::renderable::years::YearsRangeHtml;
+use serde::{Deserialize, Serialize};
+use tide::{Body, Request, Response, Result, StatusCode};
+
+/// Query of the jeppesen route, with the filter : ?time=utc or ?time=local
+#[derive(Deserialize, Default)]
+struct TimeQuery {
+    time: Option<String>,
+}
+
+/// Header of the responses served from the last good version of the logbook
+const STALE_ERRORS_HEADER: &str = "X-Logbook-Errors";
+
+/// The filtered flight list of a request
+struct ApiFlightList {
+    flight_list: FlightList,
+    // errors of the newer version of the logbook, when an older one is served
+    stale_errors: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct FlightsJson<'a> {
+    flights: &'a [Flight],
+    previous_experience: &'a [PreviousExperience],
+}
+
+#[derive(Serialize, Default)]
+struct ErrorJson<'a> {
+    error: String,
+    diagnostics: Vec<DiagnosticJson<'a>>,
+}
+
+/// The flights and the previous experience entries
+pub async fn api_flights(req: Request<AppState>) -> Result<Response> {
+    let api = match filtered_flight_list(&req).await {
+        Ok(api) => api,
+        Err(response) => return Ok(*response),
+    };
+    flight_list_response(
+        &api,
+        &FlightsJson {
+            flights: &api.flight_list.flights,
+            previous_experience: &api.flight_list.previous_experience,
+        },
+    )
+}
+
+/// The aircraft models, with their family and immatriculations
+pub async fn api_aircrafts(req: Request<AppState>) -> Result<Response> {
+    match filtered_flight_list(&req).await {
+        Ok(api) => flight_list_response(&api, &api.flight_list.aircrafts),
+        Err(response) => Ok(*response),
+    }
+}
+
+/// The airports of the catalog and of the flights
+pub async fn api_airports(req: Request<AppState>) -> Result<Response> {
+    match filtered_flight_list(&req).await {
+        Ok(api) => flight_list_response(&api, &api.flight_list.airports),
+        Err(response) => Ok(*response),
+    }
+}
+
+/// The books and pages of the Jeppesen logbook, with their totals
+pub async fn api_jeppesen(req: Request<AppState>) -> Result<Response> {
+    let query: TimeQuery = req.query().unwrap_or_default();
+    let time_base = match query.time.as_deref().map(TimeBase::from_name) {
+        Some(Ok(time_base)) => time_base,
+        Some(Err(e)) => return Ok(error_body(StatusCode::BadRequest, format!("{e}"))),
+        None => TimeBase::Utc,
+    };
+    let pagination = match req.state().pagination.try_lock() {
+        Some(pagination) => *pagination,
+        None => {
+            return Ok(error_body(
+                StatusCode::ServiceUnavailable,
+                blocked("pagination"),
+            ))
+        }
+    };
+    let api = match filtered_flight_list(&req).await {
+        Ok(api) => api,
+        Err(response) => return Ok(*response),
+    };
+    let shelf = JeppesenBookShelfHtml::build_from(&api.flight_list, time_base, pagination);
+    flight_list_response(&api, &shelf)
+}
+
+/// The totals of each month and year
+pub async fn api_years(req: Request<AppState>) -> Result<Response> {
+    let api = match filtered_flight_list(&req).await {
+        Ok(api) => api,
+        Err(response) => return Ok(*response),
+    };
+    match YearsRangeHtml::from_flight_list(&api.flight_list) {
+        Ok(years) => flight_list_response(&api, &years),
+        Err(e) => Ok(error_body(
+            StatusCode::InternalServerError,
+            format!("{e:#}"),
+        )),
+    }
+}
+
+/// The totals of each aircraft model, family and registration
+pub async fn api_aircraft_totals(req: Request<AppState>) -> Result<Response> {
+    match filtered_flight_list(&req).await {
+        Ok(api) => flight_list_response(&api, &AircraftsHtml::from_flight_list(&api.flight_list)),
+        Err(response) => Ok(*response),
+    }
+}
+
+/// The flight list of the appstate, filtered with the parameters of the query.
+/// Returns instead the error response if it cannot be built.
+async fn filtered_flight_list(
+    req: &Request<AppState>,
+) -> std::result::Result<ApiFlightList, Box<Response>> {
+    let appstate = req.state();
+    let filter = match req
+        .query::<RawFilterParams>()
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .and_then(|raw_filter_params| FlightFilter::try_from_raw(&raw_filter_params))
+    {
+        Ok(filter) => filter,
+        Err(e) => {
+            return Err(Box::new(error_body(
+                StatusCode::BadRequest,
+                format!("{e:#}"),
+            )))
+        }
+    };
+    // waits for a load in progress
+    let mut cache = appstate.flight_list.lock().await;
+    let logbook = cache.logbook().to_string();
+    let mut flight_list = match cache.flight_list() {
+        Ok(flight_list) => flight_list.clone(),
+        Err(LoadFailure::Errors(diagnostics)) => {
+            let error = ErrorJson {
+                error: format!("Errors in the logbook {logbook}"),
+                diagnostics: diagnostics.iter().map(DiagnosticJson::from).collect(),
+            };
+            return Err(Box::new(error_response(
+                StatusCode::UnprocessableEntity,
+                &error,
+            )));
+        }
+        Err(LoadFailure::Unreadable(message)) => {
+            return Err(Box::new(error_body(
+                StatusCode::InternalServerError,
+                message.clone(),
+            )))
+        }
+    };
+    flight_list.filter(&filter);
+    let stale_errors = cache
+        .stale_failure()
+        .map(|failure| failure.messages().len());
+    Ok(ApiFlightList {
+        flight_list,
+        stale_errors,
+    })
+}
+
+/// The JSON built from a flight list, marked if the logbook is stale
+fn flight_list_response(api: &ApiFlightList, value: &impl Serialize) -> Result<Response> {
+    let mut response = json_response(StatusCode::Ok, value)?;
+    if let Some(errors) = api.stale_errors {
+        response.insert_header(STALE_ERRORS_HEADER, errors.to_string());
+    }
+    Ok(response)
+}
+
+fn json_response(status: StatusCode, value: &impl Serialize) -> Result<Response> {
+    Ok(Response::builder(status)
+        .body(Body::from_json(value)?)
+        .build())
+}
+
+fn error_response(status: StatusCode, error: &ErrorJson) -> Response {
+    let body = Body::from_json(error).unwrap_or_else(|_| Body::empty());
+    Response::builder(status).body(body).build()
+}
+
+/// The response of an error without diagnostics
+fn error_body(status: StatusCode, error: String) -> Response {
+    let error = ErrorJson {
+        error,
+        ..ErrorJson::default()
+    };
+    error_response(status, &error)
+}
+
+fn blocked(what: &str) -> String {
+    format!("state blocked cannot fetch {what}")
+}
+
+#[test]
+fn filter_parameters() {
+    use crate::flightlistgenerator::{FlightListCache, LoadOptions};
+    use crate::renderable::logbook::Pagination;
+    use async_std::sync::{Arc, Mutex};
+    use tide::http::{Method, Url};
+
+    let logbook = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/tests/logbook.yaml");
+    let mut app = tide::with_state(AppState {
+        flight_list: Arc::new(Mutex::new(FlightListCache::new(
+            logbook,
+            LoadOptions::default(),
+        ))),
+        filter: Arc::new(Mutex::new(FlightFilter::default())),
+        pagination: Arc::new(Mutex::new(Pagination::default())),
+        pilot: Arc::new(Mutex::new(Default::default())),
+        reload_events: Default::default(),
+    });
+    app.at("/api/v1/flights").get(api_flights);
+    let get = |query: &str| {
+        let url = Url::parse(&format!("http://localhost/api/v1/flights?{query}")).unwrap();
+        let request = tide::http::Request::new(Method::Get, url);
+        async_std::task::block_on(async {
+            let mut response: tide::http::Response = app.respond(request).await.unwrap();
+            let body: serde_json::Value = response.body_json().await.unwrap();
+            assert!(response.header(STALE_ERRORS_HEADER).is_none());
+            (response.status(), body)
+        })
+    };
+
+    let (status, body) = get("flights_after=2001-01-02&flights_after_enabled=true");
+    assert_eq!(status, StatusCode::Ok);
+    assert_eq!(body["flights"].as_array().unwrap().len(), 3);
+    // a disabled parameter is not read
+    let (status, body) = get("flights_after=2001-13-01");
+    assert_eq!(status, StatusCode::Ok);
+    assert_eq!(body["flights"].as_array().unwrap().len(), 4);
+    let (status, body) = get("flights_after=2001-13-01&flights_after_enabled=true");
+    assert_eq!(status, StatusCode::BadRequest);
+    assert!(body["error"].as_str().unwrap().contains("\"2001-13-01\""));
+    let (status, _) = get("flights_after_enabled=maybe");
+    assert_eq!(status, StatusCode::BadRequest);
+}
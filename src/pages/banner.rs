@@ -0,0 +1,51 @@
+use crate::applicationstate::AppState;
+use crate::renderable::banner::BannerHtml;
+use askama::Template;
+use std::future::Future;
+use std::pin::Pin;
+use tide::{Next, Request, Result};
+
+/// Middleware adding the banner of a failed reload of the logbook
+/// above the content of the html pages, which show its last good version.
+/// The events of the pages are given the version of the logbook they show.
+pub fn reload_banner<'a>(
+    req: Request<AppState>,
+    next: Next<'a, AppState>,
+) -> Pin<Box<dyn Future<Output = Result> + Send + 'a>> {
+    Box::pin(async {
+        let cache = req.state().flight_list.clone();
+        // taken before the page, a newer page only reloads once more
+        let version = {
+            let mut cache = cache.lock().await;
+            cache.refresh();
+            cache.version()
+        };
+        let mut response = next.run(req).await;
+        let is_html = response
+            .content_type()
+            .is_some_and(|mime| mime.essence() == "text/html");
+        if !is_html {
+            return Ok(response);
+        }
+        let banner = {
+            let cache = cache.lock().await;
+            match cache.stale_failure() {
+                Some(failure) => BannerHtml::new(cache.logbook(), failure).render()?,
+                None => String::new(),
+            }
+        };
+        let page = response.take_body().into_string().await?;
+        // the pages extending base.html
+        let content = "<div id=\"content\">";
+        let events = "new EventSource(\"/events\")";
+        response.set_body(
+            page.replacen(content, &format!("{banner}{content}"), 1)
+                .replacen(
+                    events,
+                    &format!("new EventSource(\"/events?version={version}\")"),
+                    1,
+                ),
+        );
+        Ok(response)
+    })
+}
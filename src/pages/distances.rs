@@ -0,0 +1,14 @@
+use super::load_filtered_flight_list;
+use crate::applicationstate::AppState;
+use crate::renderable::distances::DistancesHtml;
+use tide::{Request, Response, Result};
+
+pub async fn page_distances(req: Request<AppState>) -> Result<Response> {
+    let flight_list = match load_filtered_flight_list(req.state(), "in show_distances").await {
+        Ok(flight_list) => flight_list,
+        Err(error_page) => return Ok(*error_page),
+    };
+    // Build the distances report
+    let distances_html = DistancesHtml::from_flight_list(&flight_list);
+    Ok(distances_html.into())
+}
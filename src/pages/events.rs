@@ -0,0 +1,89 @@
+use crate::applicationstate::AppState;
+use crate::flightlistgenerator::FlightListCache;
+use async_std::channel::{bounded, Receiver, Sender, TrySendError};
+use async_std::future::timeout;
+use async_std::sync::{Arc, Mutex};
+use async_std::task::{self, sleep};
+use serde::Deserialize;
+use std::time::Duration;
+use tide::sse;
+use tide::{Request, Result};
+
+/// How often the files of the logbook are checked
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// A ping after so long without event, to notice the closed pages
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Query of the events : ?version=N, the version of the logbook shown by the page
+#[derive(Deserialize, Default)]
+struct EventsQuery {
+    version: Option<u64>,
+}
+
+/// The open pages waiting for the new versions of the logbook.
+/// A single task watches the files of the logbook for all of them.
+#[derive(Default)]
+pub struct ReloadEvents {
+    pages: Mutex<Vec<Sender<u64>>>,
+}
+
+impl ReloadEvents {
+    /// Start the task checking the files of the logbook every WATCH_INTERVAL,
+    /// it sends the new version to the open pages each time it is loaded again
+    pub fn watch(self: Arc<Self>, cache: Arc<Mutex<FlightListCache>>) {
+        task::spawn(async move {
+            let mut version = cache.lock().await.version();
+            loop {
+                sleep(WATCH_INTERVAL).await;
+                let new_version = {
+                    let mut cache = cache.lock().await;
+                    cache.refresh();
+                    cache.version()
+                };
+                if new_version != version {
+                    version = new_version;
+                    // a page still busy with the previous version reloads anyway
+                    self.pages.lock().await.retain(|page| {
+                        !matches!(page.try_send(version), Err(TrySendError::Closed(_)))
+                    });
+                }
+            }
+        });
+    }
+
+    async fn subscribe(&self) -> Receiver<u64> {
+        let (sender, receiver) = bounded(1);
+        self.pages.lock().await.push(sender);
+        receiver
+    }
+}
+
+/// Server-sent events of an open page : a reload event each time
+/// the logbook is loaded again after a change of its files,
+/// or at once if it has changed since the page was built.
+/// The page then shows the new version, or the banner of its errors.
+pub async fn page_events(req: Request<AppState>, sender: sse::Sender) -> Result<()> {
+    let state = req.state();
+    let receiver = state.reload_events.subscribe().await;
+    let version = state.flight_list.lock().await.version();
+    let query: EventsQuery = req.query().unwrap_or_default();
+    if query.version.is_some_and(|shown| shown != version)
+        && sender
+            .send("reload", version.to_string(), None)
+            .await
+            .is_err()
+    {
+        return Ok(());
+    }
+    loop {
+        let sent = match timeout(PING_INTERVAL, receiver.recv()).await {
+            Ok(Ok(version)) => sender.send("reload", version.to_string(), None).await,
+            Ok(Err(_)) => return Ok(()),
+            Err(_) => sender.send("ping", "", None).await,
+        };
+        // fails once the page is closed
+        if sent.is_err() {
+            return Ok(());
+        }
+    }
+}
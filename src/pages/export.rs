@@ -0,0 +1,62 @@
+use super::load_filtered_flight_list;
+use crate::applicationstate::AppState;
+use crate::exporter::flighttable::{flights_csv, flights_xlsx};
+use crate::flightlistgenerator::FlightList;
+use crate::renderable::htmlerror::HtmlError;
+use tide::{Request, Response, Result};
+
+/// The filtered flights as csv
+pub async fn page_flights_csv(req: Request<AppState>) -> Result<Response> {
+    Ok(export_response(
+        &req,
+        "in export_csv",
+        flights_csv,
+        "text/csv",
+        "flights.csv",
+    )
+    .await)
+}
+
+/// The filtered flights as an xlsx spreadsheet
+pub async fn page_flights_xlsx(req: Request<AppState>) -> Result<Response> {
+    Ok(export_response(
+        &req,
+        "in export_xlsx",
+        flights_xlsx,
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "flights.xlsx",
+    )
+    .await)
+}
+
+/// The file exported from the filtered flight list, downloaded as filename.
+/// Returns the error page instead if it cannot be built.
+async fn export_response(
+    req: &Request<AppState>,
+    origin: &str,
+    export: fn(&FlightList) -> anyhow::Result<Vec<u8>>,
+    content_type: &str,
+    filename: &str,
+) -> Response {
+    let flight_list = match load_filtered_flight_list(req.state(), origin).await {
+        Ok(flight_list) => flight_list,
+        Err(error_page) => return *error_page,
+    };
+    match export(&flight_list) {
+        Ok(content) => Response::builder(200)
+            .body(content)
+            .content_type(content_type)
+            .header(
+                "Content-Disposition",
+                format!("attachment; filename=\"{filename}\""),
+            )
+            .build(),
+        Err(e) => {
+            let error = HtmlError {
+                message_1: format!("Error : {e:#}"),
+                message_2: origin.into(),
+            };
+            error.into()
+        }
+    }
+}
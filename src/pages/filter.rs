@@ -6,19 +6,22 @@ use tide::{Request, Response, Result};
 pub async fn page_filter(request: Request<AppState>) -> Result<Response> {
     let appstate = request.state();
     if let Some(mut appstate_filter) = appstate.filter.try_lock() {
-        match request.query() {
-            // If we have a valid query,
-            // we update the appstate with the new filter
-            Ok(raw_filter_params) => {
-                let new_filter = FlightFilter::from_raw(raw_filter_params);
-                appstate_filter.update_from(&new_filter);
-            }
-            Err(e) => {
-                // todo remove
-                println!("Error with RawFilterParams {e:?}");
-            }
-        } // match
-          //let filter_template = FlightFilter {};
+        // without query, the current filter is only shown
+        if request.url().query().is_some() {
+            match request.query() {
+                // If we have a valid query,
+                // we update the appstate with the new filter
+                Ok(raw_filter_params) => {
+                    let new_filter = FlightFilter::from_raw(raw_filter_params);
+                    appstate_filter.update_from(&new_filter);
+                }
+                Err(e) => {
+                    // todo remove
+                    println!("Error with RawFilterParams {e:?}");
+                }
+            } // match
+        }
+        //let filter_template = FlightFilter {};
         let response = appstate_filter.clone();
         Ok(response.into())
     } else {
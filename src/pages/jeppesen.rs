@@ -1,48 +1,101 @@
+use super::load_filtered_flight_list;
 use crate::applicationstate::AppState;
-use crate::flightlistgenerator::FlightList;
-use crate::renderable::filters::FlightFilter;
+use crate::exporter::pdf::jeppesen_pdf;
+use crate::flightlistgenerator::flighttime::TimeBase;
 use crate::renderable::htmlerror::HtmlError;
-use crate::renderable::logbook::JeppesenBookShelfHtml;
+use crate::renderable::logbook::{JeppesenBookShelfHtml, JeppesenPrintHtml};
+use serde::Deserialize;
 use tide::{Request, Response, Result};
 
+/// Query of the page : ?time=utc or ?time=local
+#[derive(Deserialize, Default)]
+struct JeppesenQuery {
+    time: Option<String>,
+}
+
 pub async fn page_jeppesen(req: Request<AppState>) -> Result<Response> {
-    let appstate = req.state();
-    // fetch the name of the yaml file
-    let yaml_file: String = if let Some(appstate_filename) = appstate.filename.try_lock() {
-        appstate_filename.clone()
+    match build_bookshelf(&req, "in show_jeppesen").await {
+        Ok(jepp_bookshelf) => Ok(jepp_bookshelf.into()),
+        Err(error_page) => Ok(*error_page),
+    }
+}
+
+/// The logbook laid out for printing, with the pilot identity
+pub async fn page_jeppesen_print(req: Request<AppState>) -> Result<Response> {
+    let jepp_bookshelf = match build_bookshelf(&req, "in show_jeppesen_print").await {
+        Ok(jepp_bookshelf) => jepp_bookshelf,
+        Err(error_page) => return Ok(*error_page),
+    };
+    let pilot = if let Some(appstate_pilot) = req.state().pilot.try_lock() {
+        appstate_pilot.clone()
     } else {
         let error = HtmlError {
-            message_1: "Error : state blocked cannot fetch filename".into(),
-            message_2: "in show_jeppesen".into(),
+            message_1: "Error : state blocked cannot fetch pilot".into(),
+            message_2: "in show_jeppesen_print".into(),
         };
         return Ok(error.into());
     };
+    Ok(JeppesenPrintHtml::new(jepp_bookshelf, pilot).into())
+}
 
-    // build the filter :
-    let filter = if let Some(appstate_filter) = appstate.filter.try_lock() {
-        appstate_filter.clone()
+/// The logbook as a PDF, two A4 landscape sheets per page
+pub async fn page_jeppesen_pdf(req: Request<AppState>) -> Result<Response> {
+    let jepp_bookshelf = match build_bookshelf(&req, "in show_jeppesen_pdf").await {
+        Ok(jepp_bookshelf) => jepp_bookshelf,
+        Err(error_page) => return Ok(*error_page),
+    };
+    let pilot = if let Some(appstate_pilot) = req.state().pilot.try_lock() {
+        appstate_pilot.clone()
     } else {
         let error = HtmlError {
-            message_1: "Error : state blocked cannot fetch filter".into(),
-            message_2: "in show_jeppesen".into(),
+            message_1: "Error : state blocked cannot fetch pilot".into(),
+            message_2: "in show_jeppesen_pdf".into(),
         };
         return Ok(error.into());
     };
-    // build the flight list
-    let mut flight_list = match FlightList::load_from_yaml(yaml_file) {
-        Ok(flight_list) => flight_list,
-        Err(e) => {
-            let error_message = format!("{e:#?}");
-            let jeppesen_error = HtmlError {
-                message_1: error_message,
-                message_2: "in show_jeppesen".into(),
+    Ok(Response::builder(200)
+        .body(jeppesen_pdf(&jepp_bookshelf, &pilot))
+        .content_type("application/pdf")
+        .header("Content-Disposition", "inline; filename=\"logbook.pdf\"")
+        .build())
+}
+
+/// The filtered flight list of the appstate, paginated.
+/// Returns the error page instead if it cannot be built.
+async fn build_bookshelf(
+    req: &Request<AppState>,
+    origin: &str,
+) -> std::result::Result<JeppesenBookShelfHtml, Box<Response>> {
+    let appstate = req.state();
+    // times in UTC by default
+    let query: JeppesenQuery = req.query().unwrap_or_default();
+    let time_base = match query.time.as_deref().map(TimeBase::from_name) {
+        Some(Ok(time_base)) => time_base,
+        Some(Err(e)) => {
+            let error = HtmlError {
+                message_1: format!("Error : {e}"),
+                message_2: origin.into(),
             };
-            return Ok(jeppesen_error.into());
+            return Err(Box::new(error.into()));
         }
+        None => TimeBase::Utc,
+    };
+    // fetch the pagination
+    let pagination = if let Some(appstate_pagination) = appstate.pagination.try_lock() {
+        *appstate_pagination
+    } else {
+        let error = HtmlError {
+            message_1: "Error : state blocked cannot fetch pagination".into(),
+            message_2: origin.into(),
+        };
+        return Err(Box::new(error.into()));
     };
-    // filter the flightlist :
-    flight_list.filter(&filter);
+    // build the filtered flight list
+    let flight_list = load_filtered_flight_list(appstate, origin).await?;
     // build the bookshelf
-    let jepp_bookshelf = JeppesenBookShelfHtml::build_from(&flight_list);
-    Ok(jepp_bookshelf.into())
+    Ok(JeppesenBookShelfHtml::build_from(
+        &flight_list,
+        time_base,
+        pagination,
+    ))
 }
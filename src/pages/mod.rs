@@ -1,15 +1,80 @@
 pub mod aircrafts;
+pub mod api;
+pub mod banner;
+pub mod distances;
+pub mod events;
+pub mod export;
 pub mod families;
 pub mod filter;
 pub mod home;
 pub mod jeppesen;
 pub mod years;
 
+use crate::applicationstate::AppState;
+use crate::flightlistgenerator::{FlightList, LoadFailure};
+use crate::renderable::diagnostics::DiagnosticsHtml;
+use crate::renderable::htmlerror::HtmlError;
+use tide::Response;
+
+/// The flight list of the appstate, loaded again if the logbook has changed.
+/// If no version without errors has been loaded, returns instead
+/// the page listing the errors.
+/// origin : the page calling, shown in the error page
+async fn load_flight_list(
+    appstate: &AppState,
+    origin: &str,
+) -> std::result::Result<FlightList, Box<Response>> {
+    // waits for a load in progress
+    let mut cache = appstate.flight_list.lock().await;
+    let logbook = cache.logbook().to_string();
+    match cache.flight_list() {
+        Ok(flight_list) => Ok(flight_list.clone()),
+        Err(LoadFailure::Errors(diagnostics)) => Err(Box::new(
+            DiagnosticsHtml::from_diagnostics(&logbook, diagnostics).into(),
+        )),
+        Err(LoadFailure::Unreadable(message)) => {
+            let error = HtmlError {
+                message_1: message.clone(),
+                message_2: origin.into(),
+            };
+            Err(Box::new(error.into()))
+        }
+    }
+}
+
+/// The flight list of the appstate, filtered with the filter of the appstate.
+/// If it cannot be built, returns instead the page showing why.
+async fn load_filtered_flight_list(
+    appstate: &AppState,
+    origin: &str,
+) -> std::result::Result<FlightList, Box<Response>> {
+    // build the filter :
+    let filter = if let Some(appstate_filter) = appstate.filter.try_lock() {
+        appstate_filter.clone()
+    } else {
+        let error = HtmlError {
+            message_1: "Error : state blocked cannot fetch filter".into(),
+            message_2: origin.into(),
+        };
+        return Err(Box::new(error.into()));
+    };
+    let mut flight_list = load_flight_list(appstate, origin).await?;
+    flight_list.filter(&filter);
+    Ok(flight_list)
+}
+
 pub mod allpages {
     pub use super::aircrafts::page_aircrafts;
+    pub use super::api::{
+        api_aircraft_totals, api_aircrafts, api_airports, api_flights, api_jeppesen, api_years,
+    };
+    pub use super::banner::reload_banner;
+    pub use super::distances::page_distances;
+    pub use super::events::page_events;
+    pub use super::export::{page_flights_csv, page_flights_xlsx};
     pub use super::families::page_families;
     pub use super::filter::page_filter;
     pub use super::home::page_home;
-    pub use super::jeppesen::page_jeppesen;
+    pub use super::jeppesen::{page_jeppesen, page_jeppesen_pdf, page_jeppesen_print};
     pub use super::years::page_years;
 }
@@ -1,22 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::flightlistgenerator::{flighttime::FlightTime, FlightList};
 use askama::Template;
+use serde::Serialize;
 
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "aircrafts.html")]
 pub struct AircraftsHtml {
     models: Vec<AircraftModelHtml>,
     total_time_all_models: FlightTime,
     families: Vec<AircraftFamilyHtml>,
     total_time_all_families: FlightTime,
+    registrations: Vec<RegistrationHtml>,
 }
 
+#[derive(Serialize)]
 struct AircraftModelHtml {
     name: String,
     time: FlightTime,
 }
 
+/// Block and airborne time of an aircraft, for its maintenance.
+/// The flights without take-off and landing times count
+/// their block time as airborne.
+#[derive(Serialize)]
+struct RegistrationHtml {
+    name: String,
+    time: FlightTime,
+    airborne_time: FlightTime,
+}
+
+#[derive(Serialize)]
 struct AircraftFamilyHtml {
     name: String,
     time: FlightTime,
@@ -46,6 +60,15 @@ impl AircraftsHtml {
             .collect::<Vec<AircraftModelHtml>>();
         list_by_ac_model.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
+        // the previous experience has no model, it is on its own line
+        let brought_forward = flight_list.previous_experience_total().total_flight_time;
+        if brought_forward != FlightTime::ZERO {
+            list_by_ac_model.push(AircraftModelHtml {
+                name: "previous experience".to_string(),
+                time: brought_forward,
+            });
+        }
+
         let mut total_time_all_models = FlightTime::ZERO;
         for ac_model in list_by_ac_model.iter() {
             total_time_all_models += ac_model.time;
@@ -76,16 +99,44 @@ impl AircraftsHtml {
             .collect::<Vec<AircraftFamilyHtml>>();
         list_by_families.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
+        if brought_forward != FlightTime::ZERO {
+            list_by_families.push(AircraftFamilyHtml {
+                name: "previous experience".to_string(),
+                time: brought_forward,
+            });
+        }
+
         let mut total_time_all_families = FlightTime::ZERO;
         for ac_family in list_by_families.iter() {
             total_time_all_families += ac_family.time;
         }
 
+        // --------
+        // registrations
+        // --------
+        let mut dictionary_by_registration = BTreeMap::new();
+        for flight in flight_list.iter_flights_without_sims() {
+            let (time, airborne_time) = dictionary_by_registration
+                .entry(&flight.immatriculation)
+                .or_insert((FlightTime::ZERO, FlightTime::ZERO));
+            *time += flight.total_flight_time;
+            *airborne_time += flight.airborne_time.unwrap_or(flight.total_flight_time);
+        }
+        let registrations = dictionary_by_registration
+            .into_iter()
+            .map(|(registration, (time, airborne_time))| RegistrationHtml {
+                name: registration.to_string(),
+                time,
+                airborne_time,
+            })
+            .collect();
+
         AircraftsHtml {
             models: list_by_ac_model,
             total_time_all_models,
             families: list_by_families,
             total_time_all_families,
+            registrations,
         }
     }
 }
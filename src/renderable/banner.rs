@@ -0,0 +1,19 @@
+use crate::flightlistgenerator::LoadFailure;
+use askama::Template;
+
+/// Banner above the pages when the last change of the logbook cannot be loaded
+#[derive(Template, Debug)]
+#[template(path = "banner.html")]
+pub struct BannerHtml {
+    logbook: String,
+    messages: Vec<String>,
+}
+
+impl BannerHtml {
+    pub fn new(logbook: &str, failure: &LoadFailure) -> Self {
+        BannerHtml {
+            logbook: logbook.to_string(),
+            messages: failure.messages(),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::flightlistgenerator::diagnostic::Diagnostics;
+use askama::Template;
+
+/// Page listing all the problems found in the logbook
+#[derive(Template, Debug)]
+#[template(path = "diagnostics.html")]
+pub struct DiagnosticsHtml {
+    filename: String,
+    lines: Vec<DiagnosticLineHtml>,
+}
+
+#[derive(Debug)]
+struct DiagnosticLineHtml {
+    severity: String,
+    location: String,
+    flight_index: String,
+    date_start: String,
+    message: String,
+    snippet: String,
+}
+
+impl DiagnosticsHtml {
+    pub fn from_diagnostics(filename: &str, diagnostics: &Diagnostics) -> Self {
+        let lines = diagnostics
+            .iter()
+            .map(|diagnostic| DiagnosticLineHtml {
+                severity: diagnostic.severity.to_string(),
+                location: diagnostic
+                    .position
+                    .map(|position| {
+                        format!("{}:{}:{}", diagnostic.file, position.line, position.column)
+                    })
+                    .unwrap_or_else(|| diagnostic.file.clone()),
+                flight_index: diagnostic
+                    .flight_index
+                    .map(|index| index.to_string())
+                    .unwrap_or_default(),
+                date_start: diagnostic.date_start.clone().unwrap_or_default(),
+                message: diagnostic.message.clone(),
+                snippet: diagnostic.snippet.clone().unwrap_or_default(),
+            })
+            .collect();
+        DiagnosticsHtml {
+            filename: filename.to_string(),
+            lines,
+        }
+    }
+}
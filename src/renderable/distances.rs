@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use crate::flightlistgenerator::FlightList;
+use askama::Template;
+
+/// Great circle distance flown, in nautical miles,
+/// in total, by year, by aircraft model and by registration
+#[derive(Template)]
+#[template(path = "distances.html")]
+pub struct DistancesHtml {
+    total_nm: u32,
+    years: Vec<DistanceHtml>,
+    models: Vec<DistanceHtml>,
+    registrations: Vec<DistanceHtml>,
+    // flights without the position of an airport
+    flights_without_distance: usize,
+}
+
+struct DistanceHtml {
+    name: String,
+    flights: usize,
+    distance_nm: u32,
+}
+
+impl DistancesHtml {
+    pub fn from_flight_list(flight_list: &FlightList) -> Self {
+        let mut total_nm = 0;
+        let mut flights_without_distance = 0;
+        let mut by_year = BTreeMap::new();
+        let mut by_model = BTreeMap::new();
+        let mut by_registration = BTreeMap::new();
+        for flight in flight_list.iter_flights_without_sims() {
+            let Some(distance_nm) = flight.distance_nm else {
+                flights_without_distance += 1;
+                continue;
+            };
+            total_nm += distance_nm;
+            for (dictionary, name) in [
+                (&mut by_year, flight.date.year().to_string()),
+                (&mut by_model, flight.acmodel.clone()),
+                (&mut by_registration, flight.immatriculation.clone()),
+            ] {
+                let (flights, distance) = dictionary.entry(name).or_insert((0, 0));
+                *flights += 1;
+                *distance += distance_nm;
+            }
+        }
+        DistancesHtml {
+            total_nm,
+            years: distance_list(by_year),
+            models: distance_list(by_model),
+            registrations: distance_list(by_registration),
+            flights_without_distance,
+        }
+    }
+}
+
+fn distance_list(dictionary: BTreeMap<String, (usize, u32)>) -> Vec<DistanceHtml> {
+    dictionary
+        .into_iter()
+        .map(|(name, (flights, distance_nm))| DistanceHtml {
+            name,
+            flights,
+            distance_nm,
+        })
+        .collect()
+}
+
+#[test]
+fn distances_by_year_model_registration() {
+    let flight_list = FlightList::load_from_yaml(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/distances.yaml"
+    ))
+    .unwrap();
+    let leg_nm = flight_list.flights[0].distance_nm.unwrap();
+    assert!((95..=105).contains(&leg_nm));
+    let distances = DistancesHtml::from_flight_list(&flight_list);
+    let summary = |list: &[DistanceHtml]| {
+        list.iter()
+            .map(|distance| {
+                (
+                    distance.name.clone(),
+                    distance.flights,
+                    distance.distance_nm,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(distances.total_nm, 3 * leg_nm);
+    assert_eq!(
+        summary(&distances.years),
+        [
+            ("2019".to_string(), 2, 2 * leg_nm),
+            ("2020".to_string(), 1, leg_nm)
+        ]
+    );
+    assert_eq!(
+        summary(&distances.models),
+        [
+            ("C172".to_string(), 2, 2 * leg_nm),
+            ("PA28".to_string(), 1, leg_nm)
+        ]
+    );
+    assert_eq!(
+        summary(&distances.registrations),
+        [
+            ("ABCDEF".to_string(), 2, 2 * leg_nm),
+            ("FGHIJK".to_string(), 1, leg_nm)
+        ]
+    );
+    // Lyon Bron has no position, the simulator is not counted
+    assert_eq!(distances.flights_without_distance, 1);
+}
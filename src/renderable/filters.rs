@@ -1,4 +1,5 @@
 use crate::flightlistgenerator::flighttime::FlightDate;
+use anyhow::Context;
 use askama::Template;
 use serde::Deserialize;
 use time::{
@@ -19,8 +20,10 @@ pub struct FlightFilter {
     aircraft_family: String,
 }
 
-/// This struct reflects exactly the html input form
+/// This struct reflects exactly the html input form,
+/// the missing parameters are empty
 #[derive(Deserialize, Default, Debug)]
+#[serde(default)]
 pub struct RawFilterParams {
     // date show only flights before this date
     // format : YYYY-?
@@ -50,7 +53,7 @@ impl Default for FlightFilter {
 }
 
 impl FlightFilter {
-    /// Only way to create struct : from raw parts
+    /// From the raw parts of the filter form
     pub fn from_raw(params: RawFilterParams) -> Self {
         let date_format = format_description!("[year]-[month]-[day]");
         let flights_before = match Date::parse(&params.flights_before, date_format) {
@@ -78,6 +81,53 @@ impl FlightFilter {
             aircraft_family,
         }
     }
+
+    /// From the parameters of the filter form, only the enabled ones.
+    /// Unlike from_raw, an invalid date is an error.
+    pub fn try_from_raw(params: &RawFilterParams) -> anyhow::Result<Self> {
+        fn enabled(enabled: Option<bool>, value: &str) -> Option<&str> {
+            matches!(enabled, Some(true)).then_some(value)
+        }
+        FlightFilter::from_options(
+            enabled(params.flights_after_enabled, &params.flights_after),
+            enabled(params.flights_before_enabled, &params.flights_before),
+            enabled(params.aircraft_model_enabled, &params.aircraft_model),
+            enabled(params.aircraft_family_enabled, &params.aircraft_family),
+        )
+    }
+
+    /// The filter of the command line, the dates are YYYY-MM-DD.
+    /// Only the given values are enabled.
+    pub fn from_options(
+        flights_after: Option<&str>,
+        flights_before: Option<&str>,
+        aircraft_model: Option<&str>,
+        aircraft_family: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let date_format = format_description!("[year]-[month]-[day]");
+        let parse_date = |date: Option<&str>| -> anyhow::Result<Option<FlightDate>> {
+            date.map(|date| {
+                Date::parse(date, date_format)
+                    .map(FlightDate)
+                    .with_context(|| format!("invalid date {date:?}, expected YYYY-MM-DD"))
+            })
+            .transpose()
+        };
+        let flights_after = parse_date(flights_after)?;
+        let flights_before = parse_date(flights_before)?;
+        let default = FlightFilter::default();
+        Ok(FlightFilter {
+            flights_before_enabled: flights_before.is_some(),
+            flights_before: flights_before.unwrap_or(default.flights_before),
+            flights_after_enabled: flights_after.is_some(),
+            flights_after: flights_after.unwrap_or(default.flights_after),
+            aircraft_model_enabled: aircraft_model.is_some(),
+            aircraft_model: aircraft_model.unwrap_or_default().to_string(),
+            aircraft_family_enabled: aircraft_family.is_some(),
+            aircraft_family: aircraft_family.unwrap_or_default().to_string(),
+        })
+    }
+
     // todo refactor or remove
     pub fn update_from(&mut self, input_filter: &FlightFilter) {
         self.flights_before = input_filter.flights_before;
@@ -1,63 +1,159 @@
-use crate::flightlistgenerator::flighttime::FlightTime;
+use crate::flightlistgenerator::flighttime::{FlightTime, TimeBase};
+use crate::flightlistgenerator::previousexperience::PreviousExperience;
 use crate::flightlistgenerator::{flight::Flight, FlightList};
 use askama::Template;
+use serde::Serialize;
 
-#[derive(Template, Debug, Default)]
+#[derive(Template, Debug, Default, Serialize)]
 #[template(path = "jeppesen.html")]
 pub struct JeppesenBookShelfHtml {
-    bookshelf: Vec<JeppesenBook>,
+    pub(crate) bookshelf: Vec<JeppesenBook>,
+    local_time: bool,
+}
+
+/// The logbook laid out like the EASA paper logbook, to be printed :
+/// each page is a left and a right sheet, A4 landscape
+#[derive(Template, Debug)]
+#[template(path = "jeppesen_print.html")]
+pub struct JeppesenPrintHtml {
+    shelf: JeppesenBookShelfHtml,
+    pilot: PilotIdentity,
+}
+
+/// Rows of a printed sheet, flight lines and totals, at 7 mm
+/// they fill the A4 landscape sheet under the header
+const PRINT_ROWS_PER_SHEET: usize = 20;
+
+impl JeppesenPrintHtml {
+    pub fn new(shelf: JeppesenBookShelfHtml, pilot: PilotIdentity) -> Self {
+        JeppesenPrintHtml { shelf, pilot }
+    }
+
+    fn sheets<'a>(&self, page: &'a JeppesenPage) -> Vec<PageSheet<'a>> {
+        page.sheets(PRINT_ROWS_PER_SHEET)
+    }
+}
+
+/// The part of a page printed on a left and a right sheet.
+/// A page with more lines than a sheet holds goes on
+/// over the next sheets, its totals are on the last one.
+#[derive(Debug)]
+pub(crate) struct PageSheet<'a> {
+    pub(crate) lines: &'a [Flight],
+    // from 1, and the number of sheets of the page
+    pub(crate) number: usize,
+    pub(crate) count: usize,
+}
+
+impl PageSheet<'_> {
+    pub(crate) fn with_totals(&self) -> bool {
+        self.number == self.count
+    }
+}
+
+/// The pilot certifying the logbook, in the footer of the printed pages
+#[derive(Debug, Default, Clone)]
+pub struct PilotIdentity {
+    pub name: String,
+    pub licence_number: String,
+}
+
+/// Automatic page and book breaks, like a physical Jeppesen book.
+/// The end_of_page and end_of_book flags of the flights always break.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Pagination {
+    pub lines_per_page: Option<usize>,
+    pub pages_per_book: Option<usize>,
 }
 
 impl JeppesenBookShelfHtml {
-    pub fn build_from(flightlist: &FlightList) -> Self {
+    /// time_base : show the times in UTC, or in the local time of the airports
+    pub fn build_from(
+        flightlist: &FlightList,
+        time_base: TimeBase,
+        pagination: Pagination,
+    ) -> Self {
         let mut bookshelf = Vec::new();
-        let mut jeppesen_book = JeppesenBook::default();
-        let mut jeppesen_page = JeppesenPage::default();
-        // accumulators
-        let _total_this_page_current = TotalLine::ZERO;
-        //let mut total_this_book_current = TotalLine::ZERO;
-        let mut total_overall_current = TotalLine::ZERO;
+        let mut book_number = 1;
+        let mut jeppesen_book = JeppesenBook {
+            book_number,
+            ..JeppesenBook::default()
+        };
+        let mut jeppesen_page = JeppesenPage {
+            page_number: 1,
+            book_number,
+            ..JeppesenPage::default()
+        };
 
-        // marker
-        // true because we consider new book
-        //let mut new_book_found = true;
+        // the previous experience is brought forward on the first page
+        let brought_forward =
+            TotalLine::from_previous_experience(&flightlist.previous_experience_total());
+        jeppesen_page.total_from_previous_pages = brought_forward;
+        jeppesen_page.total_overall = brought_forward;
+        if flightlist.flights.is_empty() {
+            if !flightlist.previous_experience.is_empty() {
+                jeppesen_book.add_page(jeppesen_page);
+                bookshelf.push(jeppesen_book);
+            }
+            return JeppesenBookShelfHtml {
+                bookshelf,
+                local_time: time_base == TimeBase::Local,
+            };
+        }
 
         let index_last_flight = flightlist.flights.len() - 1;
         for (index, flight) in flightlist.flights.iter().enumerate() {
-            jeppesen_page.add_flight(flight);
+            let mut flight_line = flight.clone();
+            if time_base == TimeBase::Local {
+                flight_line.to_local_time();
+            }
+            jeppesen_page.add_flight(flight_line);
             jeppesen_page.total_this_page.add_flight(flight);
             jeppesen_page.total_this_book.add_flight(flight);
             jeppesen_page.total_overall.add_flight(flight);
 
+            let last_flight = index == index_last_flight;
+            let page_full = pagination
+                .lines_per_page
+                .is_some_and(|lines| jeppesen_page.flightlinelist.len() >= lines);
             // We have reached an end of page
-            if flight.end_of_page || index == index_last_flight {
+            if flight.end_of_page || flight.end_of_book || page_full || last_flight {
                 // push the page into the book
                 jeppesen_book.add_page(jeppesen_page.clone());
-                // we save the total_current_page:
-                //total_this_page_current = jeppesen_page.total_this_page;
-                // we save the total_current_page:
-                total_overall_current = jeppesen_page.total_overall;
-                //total_this_book_current = jeppesen_page.total_this_book;
+                let book_full = pagination
+                    .pages_per_book
+                    .is_some_and(|pages| jeppesen_book.pages.len() >= pages);
 
-                // clear the page
+                // clear the page, the next one starts from the totals of this one
+                let total_overall_current = jeppesen_page.total_overall;
                 jeppesen_page.clear();
                 jeppesen_page.total_overall = total_overall_current;
+                jeppesen_page.total_from_previous_pages = total_overall_current;
 
-                if flight.end_of_book || index == index_last_flight {
+                if flight.end_of_book || book_full || last_flight {
                     bookshelf.push(jeppesen_book.clone());
+                    book_number += 1;
                     jeppesen_book.clear();
+                    jeppesen_book.book_number = book_number;
+                    jeppesen_page.total_this_book = TotalLine::ZERO;
+                    jeppesen_page.page_number = 1;
                 } else {
-                    jeppesen_page.total_from_previous_pages = total_overall_current;
+                    jeppesen_page.page_number += 1;
                 }
+                jeppesen_page.book_number = book_number;
             }
         }
-        JeppesenBookShelfHtml { bookshelf }
+        JeppesenBookShelfHtml {
+            bookshelf,
+            local_time: time_base == TimeBase::Local,
+        }
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct JeppesenBook {
-    pages: Vec<JeppesenPage>,
+    pub(crate) book_number: usize,
+    pub(crate) pages: Vec<JeppesenPage>,
 }
 
 impl JeppesenBook {
@@ -69,49 +165,86 @@ impl JeppesenBook {
     }
 }
 
-#[derive(Debug, Default, Clone)]
-struct JeppesenPage {
-    flightlinelist: Vec<Flight>,
-    total_this_page: TotalLine,
-    total_from_previous_pages: TotalLine,
-    total_this_book: TotalLine,
-    total_overall: TotalLine,
-    _page_number: usize,
-    _book_number: usize, // total_final
+#[derive(Debug, Default, Clone, Serialize)]
+pub(crate) struct JeppesenPage {
+    pub(crate) flightlinelist: Vec<Flight>,
+    pub(crate) total_this_page: TotalLine,
+    pub(crate) total_from_previous_pages: TotalLine,
+    pub(crate) total_this_book: TotalLine,
+    pub(crate) total_overall: TotalLine,
+    // page number in its book, both start at 1
+    pub(crate) page_number: usize,
+    pub(crate) book_number: usize,
 }
 
 impl JeppesenPage {
-    fn add_flight(&mut self, flight: &Flight) {
-        self.flightlinelist.push(flight.clone());
+    fn add_flight(&mut self, flight: Flight) {
+        self.flightlinelist.push(flight);
     }
 
     fn clear(&mut self) {
         self.flightlinelist.clear();
         self.total_this_page = TotalLine::ZERO;
     }
+
+    /// The sheets of the page, with at most rows_per_sheet rows each :
+    /// the flight lines, and the 3 total rows on the last sheet.
+    /// A sheet must have room for more than the total rows.
+    pub(crate) fn sheets(&self, rows_per_sheet: usize) -> Vec<PageSheet<'_>> {
+        assert!(
+            rows_per_sheet > TOTAL_ROWS,
+            "a sheet of {rows_per_sheet} rows has no room for the flights and the totals"
+        );
+        let mut chunks = Vec::new();
+        let mut lines = self.flightlinelist.as_slice();
+        while lines.len() + TOTAL_ROWS > rows_per_sheet {
+            let (chunk, rest) = lines.split_at(rows_per_sheet.min(lines.len()));
+            chunks.push(chunk);
+            lines = rest;
+        }
+        chunks.push(lines);
+        let count = chunks.len();
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, lines)| PageSheet {
+                lines,
+                number: index + 1,
+                count,
+            })
+            .collect()
+    }
 }
 
+/// This page, from previous pages and overall
+const TOTAL_ROWS: usize = 3;
+
 /// The line with the total at the end of a page
 /// in the jeppesen logbook
-#[derive(Debug, Default, Copy, Clone)]
-struct TotalLine {
-    multi_pilot_time: FlightTime,
-    total_flight_time: FlightTime,
-    takeoff_day: u32,
-    takeoff_night: u32,
-    landing_day: u32,
-    landing_night: u32,
-    operational_condition_time_ifr: FlightTime,
-    operational_condition_time_night: FlightTime,
-    pilot_in_command_time: FlightTime,
-    copilot_time: FlightTime,
-    dual_time: FlightTime,
-    instructor_time: FlightTime,
-    sim_total_time_of_session: FlightTime,
+#[derive(Debug, Default, Copy, Clone, Serialize)]
+pub(crate) struct TotalLine {
+    pub(crate) single_pilot_time_se: FlightTime,
+    pub(crate) single_pilot_time_me: FlightTime,
+    pub(crate) multi_pilot_time: FlightTime,
+    pub(crate) total_flight_time: FlightTime,
+    pub(crate) takeoff_day: u32,
+    pub(crate) takeoff_night: u32,
+    pub(crate) landing_day: u32,
+    pub(crate) landing_night: u32,
+    pub(crate) operational_condition_time_ifr: FlightTime,
+    pub(crate) operational_condition_time_night: FlightTime,
+    pub(crate) pilot_in_command_time: FlightTime,
+    pub(crate) copilot_time: FlightTime,
+    pub(crate) dual_time: FlightTime,
+    pub(crate) instructor_time: FlightTime,
+    pub(crate) sim_total_time_of_session: FlightTime,
+    pub(crate) distance_nm: u32,
 }
 
 impl TotalLine {
     pub const ZERO: Self = TotalLine {
+        single_pilot_time_se: FlightTime::ZERO,
+        single_pilot_time_me: FlightTime::ZERO,
         multi_pilot_time: FlightTime::ZERO,
         total_flight_time: FlightTime::ZERO,
         takeoff_day: 0,
@@ -125,10 +258,38 @@ impl TotalLine {
         dual_time: FlightTime::ZERO,
         instructor_time: FlightTime::ZERO,
         sim_total_time_of_session: FlightTime::ZERO,
+        distance_nm: 0,
     };
 
+    fn from_previous_experience(previous: &PreviousExperience) -> Self {
+        TotalLine {
+            single_pilot_time_se: previous.single_pilot_time_se,
+            single_pilot_time_me: previous.single_pilot_time_me,
+            multi_pilot_time: previous.multi_pilot_time,
+            total_flight_time: previous.total_flight_time,
+            takeoff_day: previous.takeoff_day,
+            takeoff_night: previous.takeoff_night,
+            landing_day: previous.landing_day,
+            landing_night: previous.landing_night,
+            operational_condition_time_ifr: previous.operational_condition_time_ifr,
+            operational_condition_time_night: previous.operational_condition_time_night,
+            pilot_in_command_time: previous.pilot_in_command_time,
+            copilot_time: previous.copilot_time,
+            dual_time: previous.dual_time,
+            instructor_time: previous.instructor_time,
+            sim_total_time_of_session: previous.sim_total_time_of_session,
+            distance_nm: 0,
+        }
+    }
+
     fn add_flight(&mut self, flight: &Flight) {
         if !flight.is_sim {
+            if flight.single_pilot_time_se {
+                self.single_pilot_time_se += flight.total_flight_time;
+            }
+            if flight.single_pilot_time_me {
+                self.single_pilot_time_me += flight.total_flight_time;
+            }
             self.multi_pilot_time += flight.multi_pilot_time;
             self.total_flight_time += flight.total_flight_time;
             self.takeoff_day += flight.takeoff_day as u32;
@@ -141,8 +302,98 @@ impl TotalLine {
             self.copilot_time += flight.copilot_time;
             self.dual_time += flight.dual_time;
             self.instructor_time += flight.instructor_time;
+            self.distance_nm += flight.distance_nm.unwrap_or(0);
         } else {
             self.sim_total_time_of_session += flight.sim_total_time_of_session;
         }
     }
 }
+
+#[test]
+fn automatic_pagination() {
+    let flight_list = FlightList::load_from_yaml(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/logbook.yaml"
+    ))
+    .unwrap();
+    let numbers = |shelf: &JeppesenBookShelfHtml| {
+        shelf
+            .bookshelf
+            .iter()
+            .flat_map(|book| book.pages.iter())
+            .map(|page| {
+                (
+                    page.book_number,
+                    page.page_number,
+                    page.flightlinelist.len(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    // the end_of_page flag of the second flight
+    let shelf =
+        JeppesenBookShelfHtml::build_from(&flight_list, TimeBase::Utc, Pagination::default());
+    assert_eq!(numbers(&shelf), [(1, 1, 2), (1, 2, 2)]);
+    // one flight per page, two pages per book
+    let pagination = Pagination {
+        lines_per_page: Some(1),
+        pages_per_book: Some(2),
+    };
+    let shelf = JeppesenBookShelfHtml::build_from(&flight_list, TimeBase::Utc, pagination);
+    assert_eq!(
+        numbers(&shelf),
+        [(1, 1, 1), (1, 2, 1), (2, 1, 1), (2, 2, 1)]
+    );
+    assert_eq!(shelf.bookshelf[1].book_number, 2);
+    // the totals go on across the books
+    let last_page = &shelf.bookshelf[1].pages[0];
+    assert_eq!(
+        last_page.total_from_previous_pages.total_flight_time,
+        shelf.bookshelf[0].pages[1].total_overall.total_flight_time
+    );
+}
+
+#[test]
+fn page_sheets() {
+    let flight_list = FlightList::load_from_yaml(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/samples/tests/logbook.yaml"
+    ))
+    .unwrap();
+    let mut page = JeppesenPage::default();
+    let lines_per_sheet = |page: &JeppesenPage| {
+        page.sheets(20)
+            .iter()
+            .map(|sheet| (sheet.lines.len(), sheet.with_totals()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(lines_per_sheet(&page), [(0, true)]);
+    page.flightlinelist = flight_list
+        .flights
+        .iter()
+        .cycle()
+        .take(17)
+        .cloned()
+        .collect();
+    assert_eq!(lines_per_sheet(&page), [(17, true)]);
+    // the totals go alone on the next sheet
+    page.flightlinelist = flight_list
+        .flights
+        .iter()
+        .cycle()
+        .take(19)
+        .cloned()
+        .collect();
+    assert_eq!(lines_per_sheet(&page), [(19, false), (0, true)]);
+    page.flightlinelist = flight_list
+        .flights
+        .iter()
+        .cycle()
+        .take(45)
+        .cloned()
+        .collect();
+    assert_eq!(
+        lines_per_sheet(&page),
+        [(20, false), (20, false), (5, true)]
+    );
+}
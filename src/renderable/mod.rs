@@ -1,4 +1,7 @@
 pub mod aircrafts;
+pub mod banner;
+pub mod diagnostics;
+pub mod distances;
 pub mod familiesandmodels;
 pub mod filters;
 pub mod home;
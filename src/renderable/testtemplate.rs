@@ -10,6 +10,12 @@ pub struct TestTemplate {
     a_toto: Toto,
 }
 
+impl Default for TestTemplate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TestTemplate {
     pub fn new() -> Self {
         TestTemplate {
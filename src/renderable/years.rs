@@ -1,24 +1,27 @@
 use crate::flightlistgenerator::{flighttime::FlightTime, FlightList};
 use anyhow::{Context, Result};
 use askama::Template;
+use serde::Serialize;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct MonthHtml {
     total_month: FlightTime, // todo change
     total_this_year: FlightTime,
     total_overall: FlightTime,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct YearHtml {
     year: i32,
     months: Vec<MonthHtml>,
 }
 
-#[derive(Template, Debug)]
+#[derive(Template, Debug, Serialize)]
 #[template(path = "years.html")]
 pub struct YearsRangeHtml {
     years: Vec<YearHtml>,
+    // total time of the previous experience, counted in the overall totals
+    brought_forward: FlightTime,
 }
 
 impl YearsRangeHtml {
@@ -26,8 +29,16 @@ impl YearsRangeHtml {
         // Find the maximum and minimum
         // year in all flights
         let all_flight_year: Vec<i32> = flight_list.flights.iter().map(|f| f.date.year()).collect();
-        let year_min = *all_flight_year.iter().min().unwrap();
-        let year_max = *all_flight_year.iter().max().unwrap();
+        let brought_forward = flight_list.previous_experience_total().total_flight_time;
+        // no year without flights, a filter can leave none
+        let (Some(&year_min), Some(&year_max)) =
+            (all_flight_year.iter().min(), all_flight_year.iter().max())
+        else {
+            return Ok(YearsRangeHtml {
+                years: Vec::new(),
+                brought_forward,
+            });
+        };
 
         // Create an empty yearsrangehtml :
         let mut years = Vec::new();
@@ -42,7 +53,10 @@ impl YearsRangeHtml {
             } // for month
             years.push(YearHtml { year, months });
         } // for year
-        let mut years_range_html = YearsRangeHtml { years };
+        let mut years_range_html = YearsRangeHtml {
+            years,
+            brought_forward,
+        };
 
         // Fill
         for flight in flight_list.flights.iter() {
@@ -56,7 +70,7 @@ impl YearsRangeHtml {
 
         // Fill total years and total overall
         let mut accumulator_year;
-        let mut accumulator_overall = FlightTime::ZERO;
+        let mut accumulator_overall = brought_forward;
         for year in years_range_html.years.iter_mut() {
             accumulator_year = FlightTime::ZERO;
             for month in year.months.iter_mut() {
@@ -73,7 +87,7 @@ impl YearsRangeHtml {
     fn index_from_year(&self, target_year: i32) -> Result<usize> {
         let year_min = self
             .years
-            .get(0)
+            .first()
             .context("Error: YearsRangeHtml is empty. This should not happen")?
             .year;
         Ok((target_year - year_min) as usize)
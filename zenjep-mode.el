@@ -53,6 +53,20 @@ with the duration_total."
     (insert result)))
 
 
+;; flycheck checker, runs zenjep check on the yaml logbook.
+;; The zenjep binary must be in the exec-path.
+(with-eval-after-load 'flycheck
+  (flycheck-define-checker zenjep
+    "A yaml logbook checker using zenjep check."
+    :command ("zenjep" "check" "--file" source-original)
+    :predicate flycheck-buffer-saved-p
+    :error-patterns
+    ((error line-start (file-name) ":" line ":" column ": error" (message) line-end)
+     (warning line-start (file-name) ":" line ":" column ": warning" (message) line-end))
+    :modes (yaml-mode))
+  (add-to-list 'flycheck-checkers 'zenjep))
+
+
 ;; test zone
 - date_start: "2017-10-05 14:20"
 date_end: "2017-10-05 16:50"